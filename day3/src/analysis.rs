@@ -0,0 +1,345 @@
+use std::{collections::HashMap, fmt::Write};
+
+use serde::Serialize;
+
+use crate::DiagnosticReport;
+
+/// Statistics for a single bit position (column) of the report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BitStats {
+    pub position: usize,
+    pub ones: usize,
+    pub zeros: usize,
+    /// Share of readings with a `1` at this position
+    pub ones_ratio: f64,
+    /// Shannon entropy of the column, in bits
+    pub entropy: f64,
+}
+
+/// Pearson (phi) correlation between two bit positions.
+///
+/// `coefficient` is `None` when either column is constant, since the correlation is undefined there.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BitCorrelation {
+    pub position: usize,
+    pub other_position: usize,
+    pub coefficient: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ReadingCount {
+    /// The reading formatted as a zero-padded binary string
+    pub reading: String,
+    pub value: u16,
+    pub count: usize,
+}
+
+/// Full statistical breakdown of a [DiagnosticReport]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticAnalysis {
+    pub reading_count: usize,
+    pub bit_width: usize,
+    pub bits: Vec<BitStats>,
+    pub correlations: Vec<BitCorrelation>,
+    /// Readings that appear more than once, most frequent first
+    pub duplicates: Vec<ReadingCount>,
+    /// The `top` most frequent readings, most frequent first
+    pub most_frequent: Vec<ReadingCount>,
+}
+
+/// Shannon entropy (in bits) of a binary variable that is `1` with probability `p`
+fn binary_entropy(p: f64) -> f64 {
+    if p <= 0.0 || p >= 1.0 {
+        return 0.0;
+    }
+
+    -(p * p.log2()) - ((1.0 - p) * (1.0 - p).log2())
+}
+
+impl DiagnosticAnalysis {
+    pub fn new(diagnostic_report: &DiagnosticReport, top: usize) -> Self {
+        let values = &diagnostic_report.values;
+        let bit_width = diagnostic_report.bit_width;
+        let reading_count = values.len();
+        let bit_at = |value: u16, position: usize| (value >> (bit_width - 1 - position)) & 0x01;
+
+        let bits: Vec<BitStats> = (0..bit_width)
+            .map(|position| {
+                let ones = values
+                    .iter()
+                    .filter(|&&value| bit_at(value, position) == 1)
+                    .count();
+                let ones_ratio = if reading_count == 0 {
+                    0.0
+                } else {
+                    ones as f64 / reading_count as f64
+                };
+                BitStats {
+                    position,
+                    ones,
+                    zeros: reading_count - ones,
+                    ones_ratio,
+                    entropy: binary_entropy(ones_ratio),
+                }
+            })
+            .collect();
+
+        let mut correlations = vec![];
+        for position in 0..bit_width {
+            for other_position in (position + 1)..bit_width {
+                let both = values
+                    .iter()
+                    .filter(|&&value| {
+                        bit_at(value, position) == 1 && bit_at(value, other_position) == 1
+                    })
+                    .count();
+                correlations.push(BitCorrelation {
+                    position,
+                    other_position,
+                    coefficient: Self::phi_coefficient(
+                        reading_count,
+                        bits[position].ones,
+                        bits[other_position].ones,
+                        both,
+                    ),
+                });
+            }
+        }
+
+        let mut counts: HashMap<u16, usize> = HashMap::new();
+        for value in values {
+            *counts.entry(*value).or_insert(0) += 1;
+        }
+        let mut by_frequency: Vec<ReadingCount> = counts
+            .into_iter()
+            .map(|(value, count)| ReadingCount {
+                reading: format!("{value:0bit_width$b}"),
+                value,
+                count,
+            })
+            .collect();
+        by_frequency.sort_by(|x, y| y.count.cmp(&x.count).then(x.value.cmp(&y.value)));
+
+        let duplicates = by_frequency
+            .iter()
+            .filter(|reading| reading.count > 1)
+            .cloned()
+            .collect();
+        by_frequency.truncate(top);
+
+        Self {
+            reading_count,
+            bit_width,
+            bits,
+            correlations,
+            duplicates,
+            most_frequent: by_frequency,
+        }
+    }
+
+    /// Pearson correlation of two 0/1 columns from their one-counts and the count of rows where both are 1
+    fn phi_coefficient(n: usize, ones_a: usize, ones_b: usize, both: usize) -> Option<f64> {
+        let n = n as f64;
+        let (ones_a, ones_b, both) = (ones_a as f64, ones_b as f64, both as f64);
+        let variance = ones_a * (n - ones_a) * ones_b * (n - ones_b);
+        if variance == 0.0 {
+            return None;
+        }
+
+        Some((n * both - ones_a * ones_b) / variance.sqrt())
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail
+        writeln!(
+            out,
+            "Readings: {} ({} bits wide)\n",
+            self.reading_count, self.bit_width
+        )
+        .unwrap();
+
+        writeln!(out, "Bit statistics:").unwrap();
+        writeln!(
+            out,
+            "{:>8} {:>8} {:>8} {:>10} {:>10}",
+            "position", "ones", "zeros", "ones_ratio", "entropy"
+        )
+        .unwrap();
+        for bit in &self.bits {
+            writeln!(
+                out,
+                "{:>8} {:>8} {:>8} {:>10.4} {:>10.4}",
+                bit.position, bit.ones, bit.zeros, bit.ones_ratio, bit.entropy
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "\nBit correlation:").unwrap();
+        write!(out, "{:>8}", "").unwrap();
+        for position in 0..self.bit_width {
+            write!(out, " {position:>7}").unwrap();
+        }
+        writeln!(out).unwrap();
+        for position in 0..self.bit_width {
+            write!(out, "{position:>8}").unwrap();
+            for other_position in 0..self.bit_width {
+                match self.coefficient(position, other_position) {
+                    Some(coefficient) => write!(out, " {coefficient:>7.3}").unwrap(),
+                    None => write!(out, " {:>7}", "-").unwrap(),
+                }
+            }
+            writeln!(out).unwrap();
+        }
+
+        writeln!(out, "\nDuplicate readings:").unwrap();
+        if self.duplicates.is_empty() {
+            writeln!(out, "  (none)").unwrap();
+        }
+        for reading in &self.duplicates {
+            writeln!(out, "  {} x{}", reading.reading, reading.count).unwrap();
+        }
+
+        writeln!(out, "\nMost frequent readings:").unwrap();
+        for reading in &self.most_frequent {
+            writeln!(out, "  {} x{}", reading.reading, reading.count).unwrap();
+        }
+
+        out
+    }
+
+    /// Formats the analysis as a single long-format CSV table with one statistic per row
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("metric,position,other_position,reading,value\n");
+        for bit in &self.bits {
+            writeln!(out, "ones_ratio,{},,,{}", bit.position, bit.ones_ratio).unwrap();
+            writeln!(out, "entropy,{},,,{}", bit.position, bit.entropy).unwrap();
+        }
+        for correlation in &self.correlations {
+            let coefficient = match correlation.coefficient {
+                Some(coefficient) => coefficient.to_string(),
+                None => String::new(),
+            };
+            writeln!(
+                out,
+                "correlation,{},{},,{coefficient}",
+                correlation.position, correlation.other_position
+            )
+            .unwrap();
+        }
+        for reading in &self.duplicates {
+            writeln!(out, "duplicate,,,{},{}", reading.reading, reading.count).unwrap();
+        }
+        for reading in &self.most_frequent {
+            writeln!(out, "most_frequent,,,{},{}", reading.reading, reading.count).unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Looks up the correlation between two positions; a position is fully correlated with itself
+    fn coefficient(&self, position: usize, other_position: usize) -> Option<f64> {
+        if position == other_position {
+            return Some(1.0);
+        }
+        let (a, b) = if position < other_position {
+            (position, other_position)
+        } else {
+            (other_position, position)
+        };
+
+        self.correlations
+            .iter()
+            .find(|correlation| correlation.position == a && correlation.other_position == b)
+            .and_then(|correlation| correlation.coefficient)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn test_binary_entropy() {
+        assert_eq!(binary_entropy(0.0), 0.0);
+        assert_eq!(binary_entropy(1.0), 0.0);
+        assert!((binary_entropy(0.5) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_bit_stats() {
+        let report = DiagnosticReport::from_str(TEST_INPUT).unwrap();
+        let analysis = DiagnosticAnalysis::new(&report, 3);
+        let expected_ones = [7, 5, 8, 7, 5];
+
+        assert_eq!(analysis.reading_count, 12);
+        for (bit, expected) in analysis.bits.iter().zip(expected_ones) {
+            assert_eq!(bit.ones, expected);
+            assert_eq!(bit.zeros, 12 - expected);
+            assert!((bit.ones_ratio - expected as f64 / 12.0).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_correlations() {
+        let report = DiagnosticReport::from_str("11\n00\n10\n01\n").unwrap();
+        let analysis = DiagnosticAnalysis::new(&report, 3);
+        assert_eq!(analysis.correlations.len(), 1);
+        assert_eq!(analysis.correlations[0].coefficient, Some(0.0));
+
+        let report = DiagnosticReport::from_str("110\n001\n110\n001\n").unwrap();
+        let analysis = DiagnosticAnalysis::new(&report, 3);
+        assert_eq!(analysis.coefficient(0, 1), Some(1.0));
+        assert_eq!(analysis.coefficient(2, 0), Some(-1.0));
+
+        let report = DiagnosticReport::from_str("10\n11\n").unwrap();
+        let analysis = DiagnosticAnalysis::new(&report, 3);
+        assert_eq!(analysis.coefficient(0, 1), None);
+    }
+
+    #[test]
+    fn test_duplicates_and_most_frequent() {
+        let report = DiagnosticReport::from_str("101\n011\n101\n111\n011\n101\n").unwrap();
+        let analysis = DiagnosticAnalysis::new(&report, 2);
+
+        assert_eq!(
+            analysis.duplicates,
+            vec![
+                ReadingCount {
+                    reading: "101".to_string(),
+                    value: 0b101,
+                    count: 3
+                },
+                ReadingCount {
+                    reading: "011".to_string(),
+                    value: 0b011,
+                    count: 2
+                },
+            ]
+        );
+        assert_eq!(analysis.most_frequent, analysis.duplicates);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let report = DiagnosticReport::from_str("10\n10\n").unwrap();
+        let csv = DiagnosticAnalysis::new(&report, 1).to_csv();
+        let expected = "metric,position,other_position,reading,value\n\
+                        ones_ratio,0,,,1\n\
+                        entropy,0,,,0\n\
+                        ones_ratio,1,,,0\n\
+                        entropy,1,,,0\n\
+                        correlation,0,1,,\n\
+                        duplicate,,,10,2\n\
+                        most_frequent,,,10,2\n";
+
+        assert_eq!(csv, expected);
+    }
+}
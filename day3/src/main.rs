@@ -1,6 +1,20 @@
+#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
-use std::fs;
+use clap::{Parser, ValueEnum};
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+mod analysis;
+mod stream;
+mod trie;
+
+use analysis::DiagnosticAnalysis;
+use stream::PowerAccumulator;
+use trie::BitTrie;
 
 struct DiagnosticReport {
     values: Vec<u16>,
@@ -8,23 +22,123 @@ struct DiagnosticReport {
 }
 
 impl DiagnosticReport {
+    /// Largest bit width that fits in a reading
+    const MAX_BIT_WIDTH: usize = u16::BITS as usize;
+
+    /// Parses plain binary readings
+    #[cfg(test)]
     fn from_str(data: &str) -> anyhow::Result<Self> {
-        let mut bit_width = 0;
+        DiagnosticReport::parse(data, 2, None)
+    }
+
+    /// Parses one reading per line in the given `radix` (2, 8 or 16).
+    ///
+    /// Lines may override the radix with a `0b`, `0o` or `0x` prefix. Unless `bit_width` is given, it is
+    /// inferred from the digit count of the first reading, so zero-padded hex or octal readings whose
+    /// width is not a multiple of 4 or 3 bits need an explicit `bit_width`.
+    fn parse(data: &str, radix: u32, bit_width: Option<usize>) -> anyhow::Result<Self> {
+        let mut bit_width = DiagnosticReport::check_bit_width(bit_width)?;
         let mut values = vec![];
-        for line in data.lines() {
-            if line.chars().count() == 0 {
+        for (line_idx, line) in data.lines().enumerate() {
+            if let Some(value) =
+                DiagnosticReport::parse_reading(line, line_idx + 1, radix, &mut bit_width)?
+            {
+                values.push(value);
+            }
+        }
+        Ok(DiagnosticReport { values, bit_width })
+    }
+
+    /// Validates an explicit bit width; returning 0 (to be inferred) if none was given
+    fn check_bit_width(bit_width: Option<usize>) -> anyhow::Result<usize> {
+        let bit_width = bit_width.unwrap_or(0);
+        if bit_width > DiagnosticReport::MAX_BIT_WIDTH {
+            anyhow::bail!(
+                "Bit width {bit_width} is larger than the maximum of {}",
+                DiagnosticReport::MAX_BIT_WIDTH
+            );
+        }
+
+        Ok(bit_width)
+    }
+
+    /// Parses a single line of input; returning `None` for blank lines.
+    ///
+    /// If `bit_width` is 0 it is set from the width of this reading.
+    fn parse_reading(
+        line: &str,
+        line_number: usize,
+        radix: u32,
+        bit_width: &mut usize,
+    ) -> anyhow::Result<Option<u16>> {
+        let line = line.trim();
+        if line.chars().count() == 0 {
+            return Ok(None);
+        }
+        let (digits, line_radix) = DiagnosticReport::strip_radix_prefix(line, radix);
+        if *bit_width == 0 {
+            *bit_width = digits.chars().count() * bits_per_digit(line_radix)?;
+            if *bit_width > DiagnosticReport::MAX_BIT_WIDTH {
+                anyhow::bail!(
+                    "Reading '{line}' on line {line_number} is wider than {} bits",
+                    DiagnosticReport::MAX_BIT_WIDTH
+                );
+            }
+        }
+        let value = u32::from_str_radix(digits, line_radix).map_err(|err| {
+            anyhow::anyhow!("Invalid reading '{line}' on line {line_number}: {err}")
+        })?;
+        if value >> *bit_width != 0 {
+            anyhow::bail!(
+                "Reading '{line}' on line {line_number} does not fit in {bit_width} bits"
+            );
+        }
+
+        Ok(Some(value as u16))
+    }
+
+    /// Splits off a `0b`, `0o` or `0x` prefix; returning the remaining digits and the radix they use
+    fn strip_radix_prefix(line: &str, default_radix: u32) -> (&str, u32) {
+        let prefixes = [
+            ("0b", 2),
+            ("0B", 2),
+            ("0o", 8),
+            ("0O", 8),
+            ("0x", 16),
+            ("0X", 16),
+        ];
+        for (prefix, radix) in prefixes {
+            // A leading `0b`/`0B` is also a valid hex number, so it only counts as a prefix outside base 16
+            if default_radix == 16 && radix == 2 {
                 continue;
             }
-            if bit_width == 0 {
-                bit_width = line.chars().count();
+            if let Some(digits) = line.strip_prefix(prefix) {
+                return (digits, radix);
             }
-            let value: u16 = u16::from_str_radix(line, 2)?;
-            values.push(value);
         }
-        Ok(DiagnosticReport { values, bit_width })
+
+        (line, default_radix)
     }
 }
 
+/// Number of bits encoded by a single digit in a supported radix
+fn bits_per_digit(radix: u32) -> anyhow::Result<usize> {
+    match radix {
+        2 => Ok(1),
+        8 => Ok(3),
+        16 => Ok(4),
+        _ => anyhow::bail!("Unsupported radix {radix}; expected 2, 8 or 16"),
+    }
+}
+
+fn parse_radix(radix: &str) -> Result<u32, String> {
+    let radix: u32 = radix
+        .parse()
+        .map_err(|_| format!("'{radix}' is not a number"))?;
+    bits_per_digit(radix).map_err(|err| err.to_string())?;
+    Ok(radix)
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 enum RatingType {
     OxygenGen,
@@ -52,7 +166,7 @@ fn common_bit_at_position(
         }
     }
 
-    return match rating_type {
+    match rating_type {
         Some(rating_type) => match rating_type {
             RatingType::OxygenGen => {
                 if count.1 >= count.0 {
@@ -76,45 +190,45 @@ fn common_bit_at_position(
                 0
             }
         }
-    };
+    }
 }
 
 struct Ratings {
-    diagnostic_report: DiagnosticReport,
+    trie: BitTrie,
     gamma: u16,
     epsilon: u16,
 }
 
 impl Ratings {
-    pub fn new(diagnostic_report: DiagnosticReport) -> Self {
-        let most_common_bits = Ratings::calculate_most_common_bits(&diagnostic_report);
+    pub fn new(diagnostic_report: &DiagnosticReport) -> Self {
+        let most_common_bits = Ratings::calculate_most_common_bits(diagnostic_report);
         let gamma = Ratings::get_gamma(&most_common_bits);
         let epsilon = Ratings::get_epsilon(&most_common_bits);
+        let trie = BitTrie::from_report(diagnostic_report);
         Self {
-            diagnostic_report,
+            trie,
             gamma,
             epsilon,
         }
     }
 
     fn calculate_most_common_bits(diagnostic_report: &DiagnosticReport) -> Vec<u16> {
-        let mut bits = vec![0; diagnostic_report.bit_width];
-        for i in 0..diagnostic_report.bit_width {
-            bits[i] = common_bit_at_position(
-                (&diagnostic_report.values, diagnostic_report.bit_width),
-                i,
-                None,
-            );
-        }
-
-        bits
+        (0..diagnostic_report.bit_width)
+            .map(|i| {
+                common_bit_at_position(
+                    (&diagnostic_report.values, diagnostic_report.bit_width),
+                    i,
+                    None,
+                )
+            })
+            .collect()
     }
 
     fn get_gamma(most_common_bits: &[u16]) -> u16 {
         let mut gamma = 0u16;
         let bit_width = most_common_bits.len();
-        for i in 0..bit_width {
-            gamma |= most_common_bits[i] << (bit_width - 1 - i);
+        for (i, bit) in most_common_bits.iter().enumerate() {
+            gamma |= bit << (bit_width - 1 - i);
         }
 
         gamma
@@ -123,8 +237,8 @@ impl Ratings {
     fn get_epsilon(most_common_bits: &[u16]) -> u16 {
         let mut epsilon = 0u16;
         let bit_width = most_common_bits.len();
-        for i in 0..bit_width {
-            let bit = if most_common_bits[i] == 1 { 0 } else { 1 };
+        for (i, most_common_bit) in most_common_bits.iter().enumerate() {
+            let bit = if *most_common_bit == 1 { 0 } else { 1 };
             epsilon |= bit << (bit_width - 1 - i);
         }
 
@@ -135,53 +249,117 @@ impl Ratings {
         self.gamma as u32 * self.epsilon as u32
     }
 
-    fn get_rating(&self, rating_type: RatingType) -> u16 {
-        let bit_width = self.diagnostic_report.bit_width;
-        let mut values = vec![];
-        for i in 0..bit_width {
-            if values.len() == 1 {
-                break;
-            }
+    /// Finds the oxygen generator or CO2 scrubber rating by walking the report's [BitTrie]
+    fn get_rating(&self, rating_type: RatingType) -> Option<u16> {
+        self.trie.rating(rating_type)
+    }
+}
 
-            let src_vec = if i == 0 {
-                &self.diagnostic_report.values
-            } else {
-                &values
-            };
-
-            let test_bit =
-                common_bit_at_position((src_vec, bit_width), i, Some(rating_type.clone()));
-            let shift_steps = bit_width - 1 - i;
-            values = src_vec
-                .iter()
-                .filter(move |&&x| {
-                    let x_bit = (x >> shift_steps) & 0x01;
-                    x_bit == test_bit
-                })
-                .map(|x| *x)
-                .collect();
-        }
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to an input file, or `-` to read from stdin
+    #[arg(short, long, value_name = "input", default_value = "input.txt")]
+    input_path: PathBuf,
+
+    /// Only compute the power consumption; streaming the input instead of keeping every reading in memory
+    #[arg(long, conflicts_with_all = ["prefix", "analyze"])]
+    power_only: bool,
+
+    /// Print how many readings start with the given bits (e.g. `101`); may be repeated
+    #[arg(short, long, value_name = "BITS")]
+    prefix: Vec<String>,
+
+    /// Radix of the readings in the input; individual lines may use a `0b`, `0o` or `0x` prefix instead
+    #[arg(short, long, value_parser = parse_radix, default_value = "2")]
+    radix: u32,
+
+    /// Width of each reading in bits; inferred from the first reading if omitted
+    #[arg(short, long)]
+    bit_width: Option<usize>,
+
+    /// Print a full per-bit analysis of the report instead of the ratings
+    #[arg(short, long)]
+    analyze: bool,
+
+    /// Output format for the analysis
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Number of most frequent readings to list in the analysis
+    #[arg(long, default_value = "5")]
+    top: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Csv,
+    Json,
+}
 
-        values[0]
+/// Opens the input file, treating `-` as stdin
+fn open_input(input_path: &Path) -> io::Result<Box<dyn BufRead>> {
+    if input_path == Path::new("-") {
+        Ok(Box::new(io::stdin().lock()))
+    } else {
+        Ok(Box::new(BufReader::new(File::open(input_path)?)))
     }
 }
 
 fn main() -> anyhow::Result<()> {
-    let input_str = fs::read_to_string("input.txt")?;
-    let diag_report = DiagnosticReport::from_str(&input_str)?;
+    let args = Args::parse();
+
+    if args.power_only {
+        let accumulator = PowerAccumulator::from_reader(
+            open_input(&args.input_path)?,
+            args.radix,
+            args.bit_width,
+        )?;
+        let power_consumption = accumulator.calculate_power_consumption();
+        println!("Power consumption: {power_consumption}");
+        return Ok(());
+    }
+
+    let mut input_str = String::new();
+    open_input(&args.input_path)?.read_to_string(&mut input_str)?;
+    let diag_report = DiagnosticReport::parse(&input_str, args.radix, args.bit_width)?;
     drop(input_str);
-    let ratings = Ratings::new(diag_report);
+
+    if args.analyze {
+        let analysis = DiagnosticAnalysis::new(&diag_report, args.top);
+        match args.format {
+            OutputFormat::Text => print!("{}", analysis.to_text()),
+            OutputFormat::Csv => print!("{}", analysis.to_csv()),
+            OutputFormat::Json => println!("{}", analysis.to_json()?),
+        }
+        return Ok(());
+    }
+
+    let ratings = Ratings::new(&diag_report);
+    drop(diag_report);
 
     let power_consumption = ratings.calculate_power_consumption();
 
     println!("Power consumption: {power_consumption}");
 
-    let oxygen_gen_rating = ratings.get_rating(RatingType::OxygenGen) as u32;
-    let co2_scrub_rating = ratings.get_rating(RatingType::CO2Scrub) as u32;
+    let oxygen_gen_rating = ratings
+        .get_rating(RatingType::OxygenGen)
+        .ok_or_else(|| anyhow::anyhow!("Diagnostic report is empty"))?
+        as u32;
+    let co2_scrub_rating = ratings
+        .get_rating(RatingType::CO2Scrub)
+        .ok_or_else(|| anyhow::anyhow!("Diagnostic report is empty"))?
+        as u32;
 
     let life_support_rating = oxygen_gen_rating * co2_scrub_rating;
     println!("Life support rating: {life_support_rating}");
 
+    for prefix in &args.prefix {
+        let count = ratings.trie.count_with_prefix(prefix);
+        println!("Readings starting with {prefix}: {count}");
+    }
+
     Ok(())
 }
 
@@ -225,6 +403,58 @@ mod test {
         assert_eq!(input_data.bit_width, expected_bit_width);
     }
 
+    #[test]
+    fn test_parse_radix() {
+        let test_data = [
+            ("04\n1E\n16\n17\n15\n0F\n07\n1C\n10\n19\n02\n0A\n", 16),
+            ("04\n36\n26\n27\n25\n17\n07\n34\n20\n31\n02\n12\n", 8),
+            (
+                "0x04\n0b11110\n0o26\n10111\n0x15\n0o17\n0b00111\n11100\n0x10\n0o31\n00010\n0x0a\n",
+                2,
+            ),
+        ];
+
+        for (data, radix) in test_data {
+            let report = DiagnosticReport::parse(data, radix, Some(5)).unwrap();
+            assert_eq!(report.values, *DIAG_VALUES, "radix {radix}");
+            assert_eq!(report.bit_width, 5);
+
+            let ratings = Ratings::new(&report);
+            assert_eq!(ratings.gamma, 0b10110);
+            assert_eq!(ratings.epsilon, 0b01001);
+            assert_eq!(ratings.get_rating(RatingType::OxygenGen), Some(23));
+            assert_eq!(ratings.get_rating(RatingType::CO2Scrub), Some(10));
+        }
+    }
+
+    #[test]
+    fn test_parse_infers_bit_width() {
+        let test_data = [("0x0ab\n", 2, 12), ("7f\n", 16, 8), ("017\n", 8, 9)];
+
+        for (data, radix, expected_bit_width) in test_data {
+            let report = DiagnosticReport::parse(data, radix, None).unwrap();
+            assert_eq!(report.bit_width, expected_bit_width, "input '{data}'");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid() {
+        let test_data = [
+            ("1F\n", 16, Some(4)),
+            ("102\n", 2, None),
+            ("0x12345\n", 2, None),
+            ("1\n", 2, Some(17)),
+            ("1\n", 10, None),
+        ];
+
+        for (data, radix, bit_width) in test_data {
+            assert!(
+                DiagnosticReport::parse(data, radix, bit_width).is_err(),
+                "input '{data}' should not parse"
+            );
+        }
+    }
+
     #[test]
     fn test_calculate_most_common_bits() {
         assert_eq!(
@@ -252,7 +482,7 @@ mod test {
 
     #[test]
     fn test_get_rating() {
-        let ratings = Ratings::new(DiagnosticReport {
+        let ratings = Ratings::new(&DiagnosticReport {
             values: DIAG_VALUES.clone(),
             bit_width: 5,
         });
@@ -260,7 +490,7 @@ mod test {
 
         for (rating_type, expected_rating) in test_data {
             let oxygen_gen_rating = ratings.get_rating(rating_type);
-            assert_eq!(oxygen_gen_rating, expected_rating);
+            assert_eq!(oxygen_gen_rating, Some(expected_rating));
         }
     }
 }
@@ -0,0 +1,126 @@
+use std::io::BufRead;
+
+use crate::DiagnosticReport;
+
+/// Computes gamma, epsilon and power consumption from a stream of readings.
+///
+/// Only the number of `1` bits seen in each column is kept, so memory use does not grow with the input.
+/// The oxygen generator and CO2 scrubber ratings need every reading and still require a full
+/// [DiagnosticReport].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PowerAccumulator {
+    radix: u32,
+    bit_width: usize,
+    ones: Vec<usize>,
+    count: usize,
+    line_number: usize,
+}
+
+impl PowerAccumulator {
+    pub fn new(radix: u32, bit_width: Option<usize>) -> anyhow::Result<Self> {
+        let bit_width = DiagnosticReport::check_bit_width(bit_width)?;
+        Ok(Self {
+            radix,
+            bit_width,
+            ones: vec![0; bit_width],
+            count: 0,
+            line_number: 0,
+        })
+    }
+
+    /// Reads every line from `reader` into a new accumulator
+    pub fn from_reader<R: BufRead>(
+        mut reader: R,
+        radix: u32,
+        bit_width: Option<usize>,
+    ) -> anyhow::Result<Self> {
+        let mut accumulator = Self::new(radix, bit_width)?;
+        let mut line = String::new();
+        while reader.read_line(&mut line)? > 0 {
+            accumulator.push_line(&line)?;
+            line.clear();
+        }
+
+        Ok(accumulator)
+    }
+
+    /// Parses a line of input and adds its reading to the column counts
+    pub fn push_line(&mut self, line: &str) -> anyhow::Result<()> {
+        self.line_number += 1;
+        if let Some(value) = DiagnosticReport::parse_reading(
+            line,
+            self.line_number,
+            self.radix,
+            &mut self.bit_width,
+        )? {
+            self.push(value);
+        }
+
+        Ok(())
+    }
+
+    fn push(&mut self, value: u16) {
+        // The bit width is only known once the first reading has been parsed
+        if self.ones.len() != self.bit_width {
+            self.ones = vec![0; self.bit_width];
+        }
+        for (position, ones) in self.ones.iter_mut().enumerate() {
+            *ones += ((value >> (self.bit_width - 1 - position)) & 0x01) as usize;
+        }
+        self.count += 1;
+    }
+
+    /// Builds gamma from the most common bit of each column; favoring 1 over 0
+    pub fn gamma(&self) -> u16 {
+        self.ones.iter().fold(0u16, |gamma, &ones| {
+            let zeros = self.count - ones;
+            (gamma << 1) | u16::from(ones >= zeros)
+        })
+    }
+
+    /// Builds epsilon from the least common bit of each column, i.e. the complement of gamma
+    pub fn epsilon(&self) -> u16 {
+        let mask = ((1u32 << self.bit_width) - 1) as u16;
+        !self.gamma() & mask
+    }
+
+    pub fn calculate_power_consumption(&self) -> u32 {
+        self.gamma() as u32 * self.epsilon() as u32
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn test_from_reader() {
+        let accumulator = PowerAccumulator::from_reader(TEST_INPUT.as_bytes(), 2, None).unwrap();
+
+        assert_eq!(accumulator.count, 12);
+        assert_eq!(accumulator.gamma(), 0b10110);
+        assert_eq!(accumulator.epsilon(), 0b01001);
+        assert_eq!(accumulator.calculate_power_consumption(), 198);
+    }
+
+    #[test]
+    fn test_hex_with_bit_width() {
+        let input = "04\n1E\n16\n17\n15\n0F\n07\n1C\n10\n19\n02\n0A\n";
+        let accumulator = PowerAccumulator::from_reader(input.as_bytes(), 16, Some(5)).unwrap();
+
+        assert_eq!(accumulator.calculate_power_consumption(), 198);
+    }
+
+    #[test]
+    fn test_invalid_line() {
+        let mut accumulator = PowerAccumulator::new(2, None).unwrap();
+        accumulator.push_line("101\n").unwrap();
+        accumulator.push_line("\n").unwrap();
+
+        let err = accumulator.push_line("1021\n").unwrap_err();
+        assert!(err.to_string().contains("line 3"), "{err}");
+    }
+}
@@ -0,0 +1,178 @@
+use crate::{DiagnosticReport, RatingType};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct TrieNode {
+    /// Indices of the child nodes for a `0` bit and a `1` bit, respectively
+    children: [Option<usize>; 2],
+    /// Number of values stored in this node's subtree
+    count: usize,
+}
+
+/// Binary prefix tree over the values of a [DiagnosticReport].
+///
+/// Every node records how many values pass through it, so bit frequencies for any prefix can be read
+/// directly from the tree instead of re-scanning the values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitTrie {
+    nodes: Vec<TrieNode>,
+    bit_width: usize,
+}
+
+impl BitTrie {
+    const ROOT: usize = 0;
+
+    pub fn new(bit_width: usize) -> Self {
+        Self {
+            nodes: vec![TrieNode::default()],
+            bit_width,
+        }
+    }
+
+    pub fn from_report(diagnostic_report: &DiagnosticReport) -> Self {
+        let mut trie = Self::new(diagnostic_report.bit_width);
+        for value in &diagnostic_report.values {
+            trie.insert(*value);
+        }
+
+        trie
+    }
+
+    /// Adds a value to the trie; reading its bits from most to least significant
+    pub fn insert(&mut self, value: u16) {
+        let mut node_idx = Self::ROOT;
+        self.nodes[node_idx].count += 1;
+        for position in 0..self.bit_width {
+            let bit = ((value >> (self.bit_width - 1 - position)) & 0x01) as usize;
+            node_idx = match self.nodes[node_idx].children[bit] {
+                Some(child_idx) => child_idx,
+                None => {
+                    let child_idx = self.nodes.len();
+                    self.nodes.push(TrieNode::default());
+                    self.nodes[node_idx].children[bit] = Some(child_idx);
+                    child_idx
+                }
+            };
+            self.nodes[node_idx].count += 1;
+        }
+    }
+
+    /// Number of values stored in the trie
+    pub fn len(&self) -> usize {
+        self.nodes[Self::ROOT].count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn child_count(&self, node_idx: usize, bit: usize) -> usize {
+        match self.nodes[node_idx].children[bit] {
+            Some(child_idx) => self.nodes[child_idx].count,
+            None => 0,
+        }
+    }
+
+    /// Counts the values starting with the given prefix of `0` and `1` characters.
+    ///
+    /// An empty prefix matches every value. Prefixes containing any other characters, or longer than the
+    /// trie's bit width, match nothing.
+    pub fn count_with_prefix(&self, prefix: &str) -> usize {
+        let mut node_idx = Self::ROOT;
+        for (position, c) in prefix.chars().enumerate() {
+            if position >= self.bit_width {
+                return 0;
+            }
+            let bit = match c {
+                '0' => 0,
+                '1' => 1,
+                _ => return 0,
+            };
+            node_idx = match self.nodes[node_idx].children[bit] {
+                Some(child_idx) => child_idx,
+                None => return 0,
+            };
+        }
+
+        self.nodes[node_idx].count
+    }
+
+    /// Finds a rating with a single walk from the root to a leaf.
+    ///
+    /// At each level the walk follows the most common bit for [RatingType::OxygenGen] (favoring 1) or the
+    /// least common bit for [RatingType::CO2Scrub] (favoring 0). Once a subtree only holds one value, or one
+    /// side of a node is empty, the only remaining branch is followed. Returns `None` if the trie is empty.
+    pub fn rating(&self, rating_type: RatingType) -> Option<u16> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let mut node_idx = Self::ROOT;
+        let mut rating = 0u16;
+        for _ in 0..self.bit_width {
+            let zeros = self.child_count(node_idx, 0);
+            let ones = self.child_count(node_idx, 1);
+            let bit = if zeros == 0 {
+                1
+            } else if ones == 0 {
+                0
+            } else {
+                match rating_type {
+                    RatingType::OxygenGen => usize::from(ones >= zeros),
+                    RatingType::CO2Scrub => usize::from(zeros > ones),
+                }
+            };
+
+            rating = (rating << 1) | bit as u16;
+            node_idx = self.nodes[node_idx].children[bit]?;
+        }
+
+        Some(rating)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const TEST_INPUT: &str =
+        "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn test_count_with_prefix() {
+        let trie = BitTrie::from_report(&DiagnosticReport::from_str(TEST_INPUT).unwrap());
+        let test_data = [
+            ("", 12),
+            ("1", 7),
+            ("0", 5),
+            ("101", 3),
+            ("10110", 1),
+            ("0000", 0),
+            ("101101", 0),
+            ("1x", 0),
+        ];
+
+        for (prefix, expected) in test_data {
+            assert_eq!(
+                trie.count_with_prefix(prefix),
+                expected,
+                "unexpected count for prefix '{prefix}'"
+            );
+        }
+    }
+
+    #[test]
+    fn test_rating() {
+        let trie = BitTrie::from_report(&DiagnosticReport::from_str(TEST_INPUT).unwrap());
+
+        assert_eq!(trie.rating(RatingType::OxygenGen), Some(23));
+        assert_eq!(trie.rating(RatingType::CO2Scrub), Some(10));
+    }
+
+    #[test]
+    fn test_rating_empty() {
+        let trie = BitTrie::new(5);
+
+        assert!(trie.is_empty());
+        assert_eq!(trie.rating(RatingType::OxygenGen), None);
+    }
+}
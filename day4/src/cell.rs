@@ -0,0 +1,104 @@
+use std::hash::Hash;
+
+use crate::BingoBoard;
+
+/// A value that can fill a space on a [BingoBoard] and be drawn; any value that can be compared and hashed
+pub trait Cell: Clone + Eq + Hash {}
+
+impl<T: Clone + Eq + Hash> Cell for T {}
+
+/// Scores a winning board; any `Fn(&BingoBoard<T>) -> u32` is a scorer, such as the functions below
+pub trait Scorer<T> {
+    fn score(&self, board: &BingoBoard<T>) -> u32;
+}
+
+impl<T, F: Fn(&BingoBoard<T>) -> u32> Scorer<T> for F {
+    fn score(&self, board: &BingoBoard<T>) -> u32 {
+        self(board)
+    }
+}
+
+/// The puzzle's score: the sum of the unmarked spaces multiplied by the value that won
+pub fn final_score(board: &BingoBoard) -> u32 {
+    board.sum_of_unmarked() * board.winning_val.unwrap_or(0)
+}
+
+/// The sum of the unmarked spaces, ignoring the value that won
+pub fn unmarked_sum(board: &BingoBoard) -> u32 {
+    board.sum_of_unmarked()
+}
+
+/// Number of spaces on the board that are not marked; the closer a board came to a full card, the lower
+/// its score
+pub fn unmarked_count<T>(board: &BingoBoard<T>) -> u32 {
+    board
+        .spaces
+        .iter()
+        .flatten()
+        .filter(|space| !space.marked)
+        .count() as u32
+}
+
+/// Number of spaces on the board that are marked
+pub fn marked_count<T>(board: &BingoBoard<T>) -> u32 {
+    board.marked_count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{input::load_token_input, pattern::WinPattern, run_game};
+
+    const WORD_INPUT: &str = "fox,owl,cat,emu,yak\n\n\
+                              cat dog owl\nemu fox ant\n\n\
+                              🐝 owl yak\nfox cat 🦊\n";
+
+    #[test]
+    fn test_word_game() {
+        let (draws, mut boards) = load_token_input(WORD_INPUT, None).unwrap();
+        let (first_winner, last_winner) = run_game(&draws, &mut boards, &WinPattern::DEFAULT);
+
+        // Board 1's column `owl cat` completes on the third draw and board 0's `cat emu` on the fourth
+        let first_winner = first_winner.unwrap();
+        assert_eq!(first_winner.winning_val, Some("cat".to_string()));
+        assert_eq!(first_winner.score_with(&unmarked_count), 3);
+        let last_winner = last_winner.unwrap();
+        assert_eq!(last_winner.winning_val, Some("emu".to_string()));
+        assert_eq!(last_winner.score_with(&unmarked_count), 2);
+        assert_eq!(last_winner.score_with(&marked_count), 4);
+    }
+
+    #[test]
+    fn test_custom_scorer() {
+        let (draws, mut boards) = load_token_input(WORD_INPUT, None).unwrap();
+        let (first_winner, _) = run_game(&draws, &mut boards, &WinPattern::DEFAULT);
+        let first_winner = first_winner.unwrap();
+
+        // Each unmarked token scores its length in characters
+        let letters = |board: &BingoBoard<String>| -> u32 {
+            board
+                .spaces
+                .iter()
+                .flatten()
+                .filter(|space| !space.marked)
+                .map(|space| space.value.chars().count() as u32)
+                .sum()
+        };
+        assert_eq!(first_winner.score_with(&letters), 1 + 3 + 1);
+        assert_eq!(first_winner.score_with(&unmarked_count), 3);
+    }
+
+    #[test]
+    fn test_numeric_scorers() {
+        let mut board = BingoBoard::new(&[vec![1, 2], vec![3, 4]]);
+        let mut placement = 0;
+        board.mark_spaces(1, [(0, 0)], &mut placement, &WinPattern::DEFAULT);
+        board.mark_spaces(3, [(1, 0)], &mut placement, &WinPattern::DEFAULT);
+
+        assert_eq!(board.winning_val, Some(3));
+        assert_eq!(board.score_with(&final_score), (2 + 4) * 3);
+        assert_eq!(board.score_with(&unmarked_sum), 2 + 4);
+        assert_eq!(board.score_with(&unmarked_count), 2);
+        assert_eq!(board.score_with(&marked_count), 2);
+    }
+}
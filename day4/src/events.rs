@@ -0,0 +1,287 @@
+use std::io::{BufRead, Write};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{cell::Cell, pattern::WinPattern, BingoBoard};
+
+/// Something that happened during a game, in the order it happened.
+///
+/// A log starts with a [GameEvent::Board] for every board, so it holds everything needed to rebuild the
+/// game's state without the original input.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum GameEvent<T = u32> {
+    /// A board's layout at the start of the game
+    Board { board: usize, values: Vec<Vec<T>> },
+    /// A value was drawn on the given turn
+    Draw { turn: usize, value: T },
+    /// The drawn value marked a space
+    Mark {
+        turn: usize,
+        board: usize,
+        row: usize,
+        col: usize,
+    },
+    /// A board completed a pattern
+    Win {
+        turn: usize,
+        board: usize,
+        value: T,
+        pattern: WinPattern,
+        placement: usize,
+        score: u32,
+    },
+    /// The drawn value did not mark any space on a board still in play
+    Miss { turn: usize, value: T },
+}
+
+impl<T> GameEvent<T> {
+    /// The turn the event happened on, or `None` for events from before the first draw
+    pub fn turn(&self) -> Option<usize> {
+        match self {
+            GameEvent::Board { .. } => None,
+            GameEvent::Draw { turn, .. }
+            | GameEvent::Mark { turn, .. }
+            | GameEvent::Win { turn, .. }
+            | GameEvent::Miss { turn, .. } => Some(*turn),
+        }
+    }
+}
+
+/// Writes the events as JSON Lines; one event per line
+pub fn write_jsonl<T: Serialize, W: Write>(
+    events: &[GameEvent<T>],
+    mut writer: W,
+) -> anyhow::Result<()> {
+    for event in events {
+        serde_json::to_writer(&mut writer, event)?;
+        writeln!(writer)?;
+    }
+    writer.flush()?;
+
+    Ok(())
+}
+
+/// Reads events written by [write_jsonl]; skipping blank lines
+pub fn read_jsonl<T: DeserializeOwned, R: BufRead>(reader: R) -> anyhow::Result<Vec<GameEvent<T>>> {
+    let mut events = vec![];
+    for (line_idx, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let event = serde_json::from_str(&line)
+            .map_err(|err| anyhow::anyhow!("Invalid event on line {}: {err}", line_idx + 1))?;
+        events.push(event);
+    }
+
+    Ok(events)
+}
+
+fn board_mut<T>(boards: &mut [BingoBoard<T>], board: usize) -> anyhow::Result<&mut BingoBoard<T>> {
+    let board_count = boards.len();
+    boards.get_mut(board).ok_or_else(|| {
+        anyhow::anyhow!("Event refers to board {board} but only {board_count} boards are set up")
+    })
+}
+
+/// The boards rebuilt by [replay]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Replay<T = u32> {
+    pub boards: Vec<BingoBoard<T>>,
+    /// The score each board's [GameEvent::Win] was logged with; `None` for boards that have not won
+    pub scores: Vec<Option<u32>>,
+}
+
+/// Rebuilds every board's state as of the end of `turn` (or the end of the game if `None`)
+pub fn replay<T: Cell>(events: &[GameEvent<T>], turn: Option<usize>) -> anyhow::Result<Replay<T>> {
+    let mut boards: Vec<BingoBoard<T>> = vec![];
+    let mut scores: Vec<Option<u32>> = vec![];
+    for event in events {
+        if turn.is_some() && event.turn() > turn {
+            break;
+        }
+
+        match event {
+            GameEvent::Board { board, values } => {
+                if *board != boards.len() {
+                    anyhow::bail!(
+                        "Expected board {} to be set up next, not {board}",
+                        boards.len()
+                    );
+                }
+                boards.push(BingoBoard::new(values));
+                scores.push(None);
+            }
+            GameEvent::Mark {
+                board, row, col, ..
+            } => {
+                let board = board_mut(&mut boards, *board)?;
+                if *row >= board.height || *col >= board.width {
+                    anyhow::bail!("Marked space ({row}, {col}) is outside the board");
+                }
+                board.mark_space(*row, *col);
+            }
+            GameEvent::Win {
+                board,
+                value,
+                pattern,
+                placement,
+                score,
+                ..
+            } => {
+                let board_idx = *board;
+                let board = board_mut(&mut boards, board_idx)?;
+                scores[board_idx] = Some(*score);
+                board.is_winner = true;
+                board.winning_val = Some(value.clone());
+                board.winning_pattern = Some(pattern.clone());
+                board.placement = Some(*placement);
+            }
+            GameEvent::Draw { .. } | GameEvent::Miss { .. } => {}
+        }
+    }
+
+    Ok(Replay { boards, scores })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cell::{final_score, unmarked_count},
+        load_input_data, play, run_game,
+    };
+
+    const TEST_INPUT: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1,99\n\n\
+                              22 13 17 11  0\n 8  2 23  4 24\n21  9 14 16  7\n 6 10  3 18  5\n 1 12 20 15 19\n\n\
+                               3 15  0  2 22\n 9 18 13 17  5\n19  8  7 25 23\n20 11 10 24  4\n14 21 16 12  6\n\n\
+                              14 21 17 24  4\n10 16 15  9 19\n18  8 23 26 20\n22 11 13  6  5\n 2  0 12  3  7";
+
+    fn play_logged() -> (Vec<BingoBoard>, Vec<GameEvent>) {
+        let (values, mut boards) = load_input_data(TEST_INPUT, None).unwrap();
+        let mut events = vec![];
+        play(
+            &values,
+            &mut boards,
+            &WinPattern::DEFAULT,
+            Some((&mut events, &final_score)),
+        );
+        (boards, events)
+    }
+
+    #[test]
+    fn test_event_log() {
+        let (_, events) = play_logged();
+
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::Board { .. }))
+                .count(),
+            3
+        );
+        assert_eq!(
+            events
+                .iter()
+                .filter(|e| matches!(e, GameEvent::Draw { .. }))
+                .count(),
+            28
+        );
+        assert_eq!(events[3], GameEvent::Draw { turn: 0, value: 7 });
+        assert_eq!(
+            events[4],
+            GameEvent::Mark {
+                turn: 0,
+                board: 0,
+                row: 2,
+                col: 4
+            }
+        );
+
+        let wins: Vec<&GameEvent> = events
+            .iter()
+            .filter(|e| matches!(e, GameEvent::Win { .. }))
+            .collect();
+        assert_eq!(wins.len(), 3);
+        assert_eq!(
+            *wins[0],
+            GameEvent::Win {
+                turn: 11,
+                board: 2,
+                value: 24,
+                pattern: WinPattern::Rows,
+                placement: 0,
+                score: 4512
+            }
+        );
+        // 99 is not on any board, and every board has already won by the time it is drawn
+        assert_eq!(
+            events.last(),
+            Some(&GameEvent::Miss {
+                turn: 27,
+                value: 99
+            })
+        );
+    }
+
+    #[test]
+    fn test_event_log_scorer() {
+        let (values, mut boards) = load_input_data(TEST_INPUT, None).unwrap();
+        let mut events = vec![];
+        play(
+            &values,
+            &mut boards,
+            &WinPattern::DEFAULT,
+            Some((&mut events, &unmarked_count)),
+        );
+
+        let first_win = events
+            .iter()
+            .find(|e| matches!(e, GameEvent::Win { .. }))
+            .unwrap();
+        assert!(matches!(first_win, GameEvent::Win { score: 13, .. }));
+        // Replaying keeps the logged scores rather than scoring the boards again
+        let replayed = replay(&events, None).unwrap();
+        assert_eq!(replayed.scores[2], Some(13));
+        assert_ne!(replayed.boards[2].score_with(&final_score), 13);
+    }
+
+    #[test]
+    fn test_jsonl_round_trip() {
+        let (_, events) = play_logged();
+        let mut buffer = vec![];
+        write_jsonl(&events, &mut buffer).unwrap();
+
+        let text = String::from_utf8(buffer.clone()).unwrap();
+        assert_eq!(text.lines().count(), events.len());
+        assert!(text.contains(r#"{"event":"draw","turn":0,"value":7}"#));
+        assert_eq!(read_jsonl(buffer.as_slice()).unwrap(), events);
+    }
+
+    #[test]
+    fn test_replay() {
+        let (boards, events) = play_logged();
+        assert_eq!(replay(&events, None).unwrap().boards, boards);
+
+        // Part way through, only the first board has won
+        let (values, mut expected) = load_input_data(TEST_INPUT, None).unwrap();
+        run_game(&values[..=11], &mut expected, &WinPattern::DEFAULT);
+        let replayed = replay(&events, Some(11)).unwrap().boards;
+        assert_eq!(replayed, expected);
+        assert!(replayed[2].is_winner);
+        assert!(!replayed[0].is_winner);
+    }
+
+    #[test]
+    fn test_replay_invalid() {
+        let events: [GameEvent; 1] = [GameEvent::Mark {
+            turn: 0,
+            board: 0,
+            row: 0,
+            col: 0,
+        }];
+        assert!(replay(&events, None).is_err());
+        assert!(read_jsonl::<u32, _>("{\"event\":\"bogus\"}\n".as_bytes()).is_err());
+    }
+}
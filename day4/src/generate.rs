@@ -0,0 +1,294 @@
+use std::{fmt::Write, ops::RangeInclusive};
+
+use rand::{
+    seq::{index, SliceRandom},
+    Rng, SeedableRng,
+};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{pattern::WinPattern, solver::solve, BingoBoard};
+
+/// Number of times to regenerate a scenario that does not meet its constraints before giving up
+const MAX_ATTEMPTS: usize = 1000;
+
+/// Settings for [generate]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScenarioConfig {
+    pub board_count: usize,
+    pub width: usize,
+    pub height: usize,
+    /// Values that can appear on boards and be drawn
+    pub values: RangeInclusive<u32>,
+    /// Number of values to draw; every value in `values` is drawn if `None`
+    pub draw_count: Option<usize>,
+    pub seed: u64,
+    /// Board that must be the first to win
+    pub first_winner: Option<usize>,
+}
+
+/// A generated game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub draws: Vec<u32>,
+    pub boards: Vec<Vec<Vec<u32>>>,
+}
+
+impl Scenario {
+    /// Formats the scenario as an input file that [crate::load_input_data] can read
+    pub fn to_input(&self) -> String {
+        let mut out = String::new();
+        let draws: Vec<String> = self.draws.iter().map(|draw| draw.to_string()).collect();
+        // Writing to a `String` cannot fail
+        writeln!(out, "{}", draws.join(",")).unwrap();
+        for board in &self.boards {
+            writeln!(out).unwrap();
+            for row in board {
+                let row: Vec<String> = row.iter().map(|value| format!("{value:>2}")).collect();
+                writeln!(out, "{}", row.join(" ")).unwrap();
+            }
+        }
+
+        out
+    }
+}
+
+/// Generates boards with unique values from the configured range and an order to draw them in.
+///
+/// The same config always gives the same scenario. When a `first_winner` is set, one of its shortest
+/// winning lines with `patterns` is moved to the front of the draw order, and the scenario is regenerated
+/// until no other board wins as early.
+pub fn generate(config: &ScenarioConfig, patterns: &[WinPattern]) -> anyhow::Result<Scenario> {
+    let (min, max) = (*config.values.start(), *config.values.end());
+    let value_count = (max - min) as usize + 1;
+    let board_size = config.width * config.height;
+    if board_size == 0 {
+        anyhow::bail!("Boards must have at least one space");
+    }
+    if board_size > value_count {
+        anyhow::bail!(
+            "A {}x{} board needs {board_size} unique values but the range {min}-{max} only has {value_count}",
+            config.width,
+            config.height
+        );
+    }
+    let draw_count = config.draw_count.unwrap_or(value_count);
+    if draw_count > value_count {
+        anyhow::bail!("Cannot draw {draw_count} unique values from the range {min}-{max}");
+    }
+    if let Some(winner) = config.first_winner {
+        if winner >= config.board_count {
+            anyhow::bail!(
+                "Board {winner} cannot win first; only {} boards are generated",
+                config.board_count
+            );
+        }
+    }
+
+    let mut rng = ChaCha8Rng::seed_from_u64(config.seed);
+    for _ in 0..MAX_ATTEMPTS {
+        let scenario = generate_once(config, patterns, value_count, draw_count, &mut rng)?;
+        if meets_constraints(config, patterns, &scenario) {
+            return Ok(scenario);
+        }
+    }
+
+    anyhow::bail!("Could not meet the scenario's constraints after {MAX_ATTEMPTS} attempts")
+}
+
+/// Picks `amount` distinct values from the `value_count` values of the configured range, in a random order,
+/// without building the whole range
+fn sample_values(
+    config: &ScenarioConfig,
+    value_count: usize,
+    amount: usize,
+    rng: &mut ChaCha8Rng,
+) -> Vec<u32> {
+    let min = *config.values.start();
+    index::sample(rng, value_count, amount)
+        .into_iter()
+        .map(|offset| min + offset as u32)
+        .collect()
+}
+
+fn generate_once(
+    config: &ScenarioConfig,
+    patterns: &[WinPattern],
+    value_count: usize,
+    draw_count: usize,
+    rng: &mut ChaCha8Rng,
+) -> anyhow::Result<Scenario> {
+    let boards: Vec<Vec<Vec<u32>>> = (0..config.board_count)
+        .map(|_| {
+            sample_values(config, value_count, config.width * config.height, rng)
+                .chunks(config.width)
+                .map(|row| row.to_vec())
+                .collect()
+        })
+        .collect();
+
+    // Only the values that will be drawn are picked; a winning line put in front can only push later ones out
+    let mut draws = sample_values(config, value_count, draw_count, rng);
+
+    if let Some(winner) = config.first_winner {
+        let lines: Vec<_> = patterns
+            .iter()
+            .flat_map(|pattern| pattern.lines(config.width, config.height))
+            .collect();
+        let shortest = lines.iter().map(|line| line.len()).min().ok_or_else(|| {
+            anyhow::anyhow!(
+                "No pattern has a winning line on a {}x{} board",
+                config.width,
+                config.height
+            )
+        })?;
+        if shortest > draw_count {
+            anyhow::bail!("Board {winner} needs at least {shortest} draws to win but only {draw_count} are drawn");
+        }
+        let candidates: Vec<_> = lines.iter().filter(|line| line.len() == shortest).collect();
+        let line = candidates[rng.gen_range(0..candidates.len())];
+
+        let mut line_values: Vec<u32> = line.iter().map(|&(y, x)| boards[winner][y][x]).collect();
+        line_values.shuffle(rng);
+        draws.retain(|value| !line_values.contains(value));
+        line_values.extend(draws);
+        draws = line_values;
+    }
+    draws.truncate(draw_count);
+
+    Ok(Scenario { draws, boards })
+}
+
+fn meets_constraints(
+    config: &ScenarioConfig,
+    patterns: &[WinPattern],
+    scenario: &Scenario,
+) -> bool {
+    let Some(winner) = config.first_winner else {
+        return true;
+    };
+    let boards: Vec<BingoBoard> = scenario
+        .boards
+        .iter()
+        .map(|board| BingoBoard::new(board))
+        .collect();
+
+    solve(&scenario.draws, &boards, patterns)[winner].placement == Some(0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{load_input_data, run_game};
+
+    fn config() -> ScenarioConfig {
+        ScenarioConfig {
+            board_count: 20,
+            width: 5,
+            height: 5,
+            values: 0..=99,
+            draw_count: None,
+            seed: 42,
+            first_winner: None,
+        }
+    }
+
+    #[test]
+    fn test_generate_round_trip() {
+        let scenario = generate(&config(), &WinPattern::DEFAULT).unwrap();
+        let (draws, boards) = load_input_data(&scenario.to_input(), None).unwrap();
+
+        assert_eq!(draws, scenario.draws);
+        assert_eq!(draws.len(), 100);
+        assert_eq!(boards.len(), 20);
+        for (board, values) in boards.iter().zip(&scenario.boards) {
+            assert_eq!(board.values(), *values);
+            let mut unique: Vec<u32> = values.iter().flatten().copied().collect();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), 25);
+        }
+    }
+
+    #[test]
+    fn test_generate_is_seeded() {
+        let scenario = generate(&config(), &WinPattern::DEFAULT).unwrap();
+        assert_eq!(scenario, generate(&config(), &WinPattern::DEFAULT).unwrap());
+
+        let other_seed = ScenarioConfig {
+            seed: 43,
+            ..config()
+        };
+        assert_ne!(
+            scenario,
+            generate(&other_seed, &WinPattern::DEFAULT).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_generate_first_winner() {
+        for winner in [0, 7, 19] {
+            let config = ScenarioConfig {
+                first_winner: Some(winner),
+                draw_count: Some(30),
+                width: 4,
+                height: 3,
+                ..config()
+            };
+            let patterns = [WinPattern::Rows, WinPattern::Diagonals];
+            let scenario = generate(&config, &patterns).unwrap();
+            assert_eq!(scenario.draws.len(), 30);
+
+            let (draws, mut boards) = load_input_data(&scenario.to_input(), None).unwrap();
+            run_game(&draws, &mut boards, &patterns);
+            assert_eq!(boards[winner].placement, Some(0));
+        }
+    }
+
+    #[test]
+    fn test_generate_huge_range() {
+        let config = ScenarioConfig {
+            board_count: 3,
+            width: 3,
+            height: 3,
+            values: 100..=4_000_000_000,
+            draw_count: Some(5),
+            ..config()
+        };
+        let scenario = generate(&config, &WinPattern::DEFAULT).unwrap();
+
+        assert_eq!(scenario.draws.len(), 5);
+        let mut draws = scenario.draws.clone();
+        draws.sort();
+        draws.dedup();
+        assert_eq!(draws.len(), 5);
+        for value in scenario
+            .draws
+            .iter()
+            .chain(scenario.boards.iter().flatten().flatten())
+        {
+            assert!(config.values.contains(value));
+        }
+    }
+
+    #[test]
+    fn test_generate_invalid() {
+        let too_small = ScenarioConfig {
+            values: 1..=10,
+            ..config()
+        };
+        assert!(generate(&too_small, &WinPattern::DEFAULT).is_err());
+
+        let missing_board = ScenarioConfig {
+            first_winner: Some(20),
+            ..config()
+        };
+        assert!(generate(&missing_board, &WinPattern::DEFAULT).is_err());
+
+        let too_few_draws = ScenarioConfig {
+            first_winner: Some(0),
+            draw_count: Some(4),
+            ..config()
+        };
+        assert!(generate(&too_few_draws, &WinPattern::DEFAULT).is_err());
+    }
+}
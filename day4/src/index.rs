@@ -0,0 +1,76 @@
+use std::collections::HashMap;
+
+use crate::{cell::Cell, BingoBoard};
+
+/// A space on one of the game's boards, as `(board, row, col)`
+pub type SpaceRef = (usize, usize, usize);
+
+/// Maps every value to the spaces holding it across all boards in a game.
+///
+/// Looking up a drawn value gives exactly the spaces it marks, so a draw does not need to visit boards
+/// that do not contain it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BingoIndex<T: Cell = u32> {
+    spaces_by_value: HashMap<T, Vec<SpaceRef>>,
+}
+
+impl<T: Cell> BingoIndex<T> {
+    pub fn new(boards: &[BingoBoard<T>]) -> Self {
+        let mut spaces_by_value: HashMap<T, Vec<SpaceRef>> = HashMap::new();
+        for (board_idx, board) in boards.iter().enumerate() {
+            for (y, row) in board.spaces.iter().enumerate() {
+                for (x, space) in row.iter().enumerate() {
+                    spaces_by_value
+                        .entry(space.value.clone())
+                        .or_default()
+                        .push((board_idx, y, x));
+                }
+            }
+        }
+
+        Self { spaces_by_value }
+    }
+
+    /// Every space holding `value`, ordered by board, then row, then column
+    pub fn spaces(&self, value: &T) -> &[SpaceRef] {
+        self.spaces_by_value
+            .get(value)
+            .map_or(&[], |spaces| spaces.as_slice())
+    }
+
+    /// The spaces holding `value`, grouped by the board they are on
+    pub fn spaces_by_board(&self, value: &T) -> impl Iterator<Item = &[SpaceRef]> {
+        self.spaces(value)
+            .chunk_by(|(board_a, _, _), (board_b, _, _)| board_a == board_b)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_spaces() {
+        let boards = [
+            BingoBoard::new(&[vec![1, 2], vec![3, 4]]),
+            BingoBoard::new(&[vec![4, 5], vec![6, 4]]),
+        ];
+        let index = BingoIndex::new(&boards);
+
+        assert_eq!(index.spaces(&1), [(0, 0, 0)]);
+        assert_eq!(index.spaces(&4), [(0, 1, 1), (1, 0, 0), (1, 1, 1)]);
+        assert!(index.spaces(&7).is_empty());
+    }
+
+    #[test]
+    fn test_spaces_by_board() {
+        let boards = [
+            BingoBoard::new(&[vec![1, 2], vec![3, 4]]),
+            BingoBoard::new(&[vec![4, 5], vec![6, 4]]),
+        ];
+        let index = BingoIndex::new(&boards);
+        let groups: Vec<&[SpaceRef]> = index.spaces_by_board(&4).collect();
+
+        assert_eq!(groups, vec![&[(0, 1, 1)][..], &[(1, 0, 0), (1, 1, 1)][..]]);
+    }
+}
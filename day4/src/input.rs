@@ -0,0 +1,460 @@
+use std::{collections::HashMap, error::Error, fmt, str::FromStr};
+
+use crate::{cell::Cell, BingoBoard};
+
+/// A problem that prevents the input from being loaded. Line numbers start at 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputError {
+    /// The input has no draw line
+    MissingDraws,
+    /// A value in the draw line is empty, or not a number in a numeric game
+    InvalidDraw { line: usize, value: String },
+    /// A board space is not a number
+    NonNumericCell { line: usize, value: String },
+    /// A board row has a different number of spaces than the board size
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A board has a different number of rows than the board size
+    IncompleteBoard {
+        line: usize,
+        board: usize,
+        expected_rows: usize,
+        found_rows: usize,
+    },
+    /// The values run out part way through a board of a fixed size
+    TruncatedBoard {
+        line: usize,
+        board: usize,
+        expected_values: usize,
+        found_values: usize,
+    },
+}
+
+impl fmt::Display for InputError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputError::MissingDraws => {
+                write!(f, "Input is empty; expected a line of drawn values")
+            }
+            InputError::InvalidDraw { line, value } => {
+                write!(f, "Line {line}: invalid drawn value '{value}'")
+            }
+            InputError::NonNumericCell { line, value } => {
+                write!(f, "Line {line}: board space '{value}' is not a number")
+            }
+            InputError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "Line {line}: board row has {found} spaces; expected {expected}"
+            ),
+            InputError::IncompleteBoard {
+                line,
+                board,
+                expected_rows,
+                found_rows,
+            } => write!(
+                f,
+                "Line {line}: board {board} has {found_rows} rows; expected {expected_rows}"
+            ),
+            InputError::TruncatedBoard {
+                line,
+                board,
+                expected_values,
+                found_values,
+            } => write!(
+                f,
+                "Line {line}: board {board} has {found_values} values; expected {expected_values}"
+            ),
+        }
+    }
+}
+
+impl Error for InputError {}
+
+/// Something suspicious about the input that does not stop the game from running
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputWarning {
+    /// A value is drawn more than once; later draws of it have no effect
+    DuplicateDraw {
+        value: u32,
+        first_turn: usize,
+        turn: usize,
+    },
+    /// A value appears on more than one space of the same board
+    DuplicateOnBoard {
+        board: usize,
+        value: u32,
+        count: usize,
+    },
+}
+
+impl fmt::Display for InputWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputWarning::DuplicateDraw {
+                value,
+                first_turn,
+                turn,
+            } => write!(
+                f,
+                "{value} is drawn on turn {turn} but was already drawn on turn {first_turn}"
+            ),
+            InputWarning::DuplicateOnBoard {
+                board,
+                value,
+                count,
+            } => write!(f, "board {board} has {count} spaces with the value {value}"),
+        }
+    }
+}
+
+fn parse_cell<T: FromStr>(value: &str, line: usize) -> Result<T, InputError> {
+    value.parse().map_err(|_| InputError::NonNumericCell {
+        line,
+        value: value.to_string(),
+    })
+}
+
+/// Loads the drawn values and the [BingoBoard]s from the input.
+///
+/// When `board_size` is `None`, boards are separated by blank lines and the board size is inferred from
+/// the first board; every other board must have the same size. When a `(width, height)` is given, the
+/// board values are read in order, regardless of line breaks, and split into boards of that size.
+pub fn load_input_data(
+    input: &str,
+    board_size: Option<(usize, usize)>,
+) -> Result<(Vec<u32>, Vec<BingoBoard>), InputError> {
+    load_cells(input, board_size)
+}
+
+/// Loads a game whose draws and spaces are arbitrary tokens, such as words or emoji.
+///
+/// The layout is the same as for [load_input_data]: the first line holds the comma separated draws and
+/// the boards' tokens are separated by whitespace, so tokens cannot contain whitespace or commas.
+pub fn load_token_input(
+    input: &str,
+    board_size: Option<(usize, usize)>,
+) -> Result<(Vec<String>, Vec<BingoBoard<String>>), InputError> {
+    load_cells(input, board_size)
+}
+
+fn load_cells<T: Cell + FromStr>(
+    input: &str,
+    board_size: Option<(usize, usize)>,
+) -> Result<(Vec<T>, Vec<BingoBoard<T>>), InputError> {
+    let mut lines = input.lines().enumerate().map(|(idx, line)| (idx + 1, line));
+    let (draw_line, values_line) = lines
+        .find(|(_, line)| !line.trim().is_empty())
+        .ok_or(InputError::MissingDraws)?;
+    let values = values_line
+        .split(',')
+        .map(|val_str| {
+            let val_str = val_str.trim();
+            let invalid = || InputError::InvalidDraw {
+                line: draw_line,
+                value: val_str.to_string(),
+            };
+            if val_str.is_empty() {
+                return Err(invalid());
+            }
+            val_str.parse::<T>().map_err(|_| invalid())
+        })
+        .collect::<Result<Vec<T>, InputError>>()?;
+
+    let boards = match board_size {
+        Some((width, height)) => load_fixed_size_boards(lines, width, height)?,
+        None => load_separated_boards(lines)?,
+    };
+
+    Ok((values, boards))
+}
+
+/// Reads boards as a stream of values, `width * height` values per board
+fn load_fixed_size_boards<'a, T: Cell + FromStr>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+    width: usize,
+    height: usize,
+) -> Result<Vec<BingoBoard<T>>, InputError> {
+    let mut boards = vec![];
+    let mut board_values: Vec<T> = Vec::with_capacity(width * height);
+    let mut last_line = 0;
+    for (line_number, line) in lines {
+        last_line = line_number;
+        for value in line.split_whitespace() {
+            board_values.push(parse_cell(value, line_number)?);
+            if board_values.len() == width * height {
+                let rows: Vec<Vec<T>> =
+                    board_values.chunks(width).map(|row| row.to_vec()).collect();
+                boards.push(BingoBoard::new(&rows));
+                board_values.clear();
+            }
+        }
+    }
+
+    if !board_values.is_empty() {
+        return Err(InputError::TruncatedBoard {
+            line: last_line,
+            board: boards.len(),
+            expected_values: width * height,
+            found_values: board_values.len(),
+        });
+    }
+
+    Ok(boards)
+}
+
+/// Reads boards separated by blank lines; the first board sets the size all boards must have
+fn load_separated_boards<'a, T: Cell + FromStr>(
+    lines: impl Iterator<Item = (usize, &'a str)>,
+) -> Result<Vec<BingoBoard<T>>, InputError> {
+    let mut boards = vec![];
+    let mut board_size: Option<(usize, usize)> = None;
+    let mut rows: Vec<Vec<T>> = vec![];
+    let mut last_line = 0;
+
+    let mut finish_board = |rows: &mut Vec<Vec<T>>,
+                            board_size: &mut Option<(usize, usize)>,
+                            line: usize|
+     -> Result<(), InputError> {
+        if rows.is_empty() {
+            return Ok(());
+        }
+        let (width, height) = *board_size.get_or_insert((rows[0].len(), rows.len()));
+        if rows.len() != height {
+            return Err(InputError::IncompleteBoard {
+                line,
+                board: boards.len(),
+                expected_rows: height,
+                found_rows: rows.len(),
+            });
+        }
+        debug_assert!(rows.iter().all(|row| row.len() == width));
+        boards.push(BingoBoard::new(rows));
+        rows.clear();
+        Ok(())
+    };
+
+    for (line_number, line) in lines {
+        if line.trim().is_empty() {
+            finish_board(&mut rows, &mut board_size, last_line)?;
+            continue;
+        }
+        last_line = line_number;
+
+        let row = line
+            .split_whitespace()
+            .map(|value| parse_cell(value, line_number))
+            .collect::<Result<Vec<T>, InputError>>()?;
+        let expected = board_size.map_or_else(
+            || rows.first().map_or(row.len(), |first_row| first_row.len()),
+            |(width, _)| width,
+        );
+        if row.len() != expected {
+            return Err(InputError::RaggedRow {
+                line: line_number,
+                expected,
+                found: row.len(),
+            });
+        }
+        if let Some((_, height)) = board_size {
+            if rows.len() == height {
+                // A board can only run long when it is not followed by a blank line
+                return Err(InputError::IncompleteBoard {
+                    line: line_number,
+                    board: boards.len(),
+                    expected_rows: height,
+                    found_rows: height + 1,
+                });
+            }
+        }
+        rows.push(row);
+    }
+    finish_board(&mut rows, &mut board_size, last_line)?;
+
+    Ok(boards)
+}
+
+/// Finds values drawn more than once, and values repeated on the same board
+pub fn find_duplicates(values: &[u32], boards: &[BingoBoard]) -> Vec<InputWarning> {
+    let mut warnings = vec![];
+
+    let mut first_turns: HashMap<u32, usize> = HashMap::new();
+    for (turn, &value) in values.iter().enumerate() {
+        match first_turns.get(&value) {
+            Some(&first_turn) => warnings.push(InputWarning::DuplicateDraw {
+                value,
+                first_turn,
+                turn,
+            }),
+            None => {
+                first_turns.insert(value, turn);
+            }
+        }
+    }
+
+    for (board_idx, board) in boards.iter().enumerate() {
+        let mut counts: HashMap<u32, usize> = HashMap::new();
+        for space in board.spaces.iter().flatten() {
+            *counts.entry(space.value).or_insert(0) += 1;
+        }
+        let mut duplicates: Vec<(u32, usize)> =
+            counts.into_iter().filter(|&(_, count)| count > 1).collect();
+        duplicates.sort();
+        warnings.extend(duplicates.into_iter().map(|(value, count)| {
+            InputWarning::DuplicateOnBoard {
+                board: board_idx,
+                value,
+                count,
+            }
+        }));
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_missing_draws() {
+        assert_eq!(load_input_data("", None), Err(InputError::MissingDraws));
+        assert_eq!(load_input_data("\n\n", None), Err(InputError::MissingDraws));
+    }
+
+    #[test]
+    fn test_invalid_draw() {
+        assert_eq!(
+            load_input_data("1,2,x,4\n\n1 2\n3 4\n", None),
+            Err(InputError::InvalidDraw {
+                line: 1,
+                value: "x".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_token_input() {
+        let (draws, boards) = load_token_input("owl, 🦊 ,cat\n\nowl cat\n🦊 emu\n", None).unwrap();
+        assert_eq!(draws, vec!["owl", "🦊", "cat"]);
+        assert_eq!(boards[0].spaces[1][0].value, "🦊");
+
+        assert_eq!(
+            load_token_input("owl,,cat\n\nowl\n", None),
+            Err(InputError::InvalidDraw {
+                line: 1,
+                value: String::new()
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_numeric_cell() {
+        assert_eq!(
+            load_input_data("1,2\n\n1 2\n3 -4\n", None),
+            Err(InputError::NonNumericCell {
+                line: 4,
+                value: "-4".to_string()
+            })
+        );
+        assert_eq!(
+            load_input_data("1,2\n1 2 3\nfour\n", Some((2, 2))),
+            Err(InputError::NonNumericCell {
+                line: 3,
+                value: "four".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_ragged_row() {
+        assert_eq!(
+            load_input_data("1,2\n\n1 2 3\n4 5\n", None),
+            Err(InputError::RaggedRow {
+                line: 4,
+                expected: 3,
+                found: 2
+            })
+        );
+        // Later boards must match the first board's width
+        assert_eq!(
+            load_input_data("1,2\n\n1 2\n3 4\n\n5 6 7\n", None),
+            Err(InputError::RaggedRow {
+                line: 6,
+                expected: 2,
+                found: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_incomplete_board() {
+        assert_eq!(
+            load_input_data("1,2\n\n1 2\n3 4\n\n5 6\n", None),
+            Err(InputError::IncompleteBoard {
+                line: 6,
+                board: 1,
+                expected_rows: 2,
+                found_rows: 1
+            })
+        );
+        assert_eq!(
+            load_input_data("1,2\n\n1 2\n3 4\n\n5 6\n7 8\n9 10\n", None),
+            Err(InputError::IncompleteBoard {
+                line: 8,
+                board: 1,
+                expected_rows: 2,
+                found_rows: 3
+            })
+        );
+        assert_eq!(
+            load_input_data("1,2\n1 2 3 4\n5 6 7\n", Some((2, 2))),
+            Err(InputError::TruncatedBoard {
+                line: 3,
+                board: 1,
+                expected_values: 4,
+                found_values: 3
+            })
+        );
+    }
+
+    #[test]
+    fn test_error_display() {
+        let err = load_input_data("1,2\n\n1 2 3\n4 5\n", None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Line 4: board row has 2 spaces; expected 3"
+        );
+
+        let err = load_input_data("1,2\n1 2 3 4\n5 6 7\n", Some((2, 2))).unwrap_err();
+        assert_eq!(err.to_string(), "Line 3: board 1 has 3 values; expected 4");
+    }
+
+    #[test]
+    fn test_find_duplicates() {
+        let (values, boards) = load_input_data("1,2,1,3\n\n1 2\n2 2\n\n3 4\n5 6\n", None).unwrap();
+        let warnings = find_duplicates(&values, &boards);
+
+        assert_eq!(
+            warnings,
+            vec![
+                InputWarning::DuplicateDraw {
+                    value: 1,
+                    first_turn: 0,
+                    turn: 2
+                },
+                InputWarning::DuplicateOnBoard {
+                    board: 0,
+                    value: 2,
+                    count: 3
+                },
+            ]
+        );
+    }
+}
@@ -1,99 +1,289 @@
-use std::fs;
+use clap::{Parser, Subcommand, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, IsTerminal},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    thread,
+};
 
+mod cell;
+mod events;
+mod generate;
+mod index;
+mod input;
+mod montecarlo;
+mod pattern;
+mod rank;
+mod render;
+mod solver;
+mod state;
+
+use cell::{final_score, marked_count, unmarked_count, unmarked_sum, Cell, Scorer};
+use events::{read_jsonl, replay, write_jsonl, GameEvent, Replay};
+use generate::{generate, ScenarioConfig};
+use index::BingoIndex;
+use input::{find_duplicates, load_input_data, load_token_input};
+use montecarlo::simulate;
+use pattern::{load_custom_patterns, PatternLines, WinLine, WinPattern};
+use rank::{RankSort, RankTable};
+use render::{terminal_width, BoardRenderer, ColorChoice, RenderStyle};
+use solver::solve;
+use state::GameState;
+
+#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct BingoSpace {
-    value: u32,
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct BingoSpace<T = u32> {
+    value: T,
     marked: bool,
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
-struct BingoBoard {
-    spaces: [[BingoSpace; 5]; 5],
+/// A bingo board and the state of its game.
+///
+/// Drawing a value marks every space holding it at once, so a board with duplicate values counts each
+/// space towards its rows and columns exactly once. Drawing a value again has no effect: its spaces are
+/// already marked, and a board can only win on a draw that marks at least one new space.
+///
+/// Spaces hold numbers by default, but any [Cell] type can be used, such as the words of a word-bingo game.
+/// Boards do not score themselves; a [Scorer] decides what a winning board is worth.
+#[derive(Debug, Clone)]
+struct BingoBoard<T = u32> {
+    spaces: Vec<Vec<BingoSpace<T>>>,
+    width: usize,
+    height: usize,
     marked_count: u32,
+    /// Number of marked spaces in each row
+    row_marks: Vec<u32>,
+    /// Number of marked spaces in each column
+    col_marks: Vec<u32>,
     is_winner: bool,
-    winning_val: Option<u32>,
+    winning_val: Option<T>,
+    winning_pattern: Option<WinPattern>,
     placement: Option<usize>,
+    /// The lines of the patterns the board plays with, other than rows and columns; built from the marked
+    /// spaces when a win is first checked, and rebuilt whenever a win is checked against other patterns
+    pattern_lines: Option<PatternLines>,
 }
 
-impl BingoBoard {
-    pub fn new(values: &[Vec<u32>]) -> Self {
-        let mut bingo_spaces = [[BingoSpace {
-            value: 0,
-            marked: false,
-        }; 5]; 5];
-        for y in 0..5 {
-            let row = &values[y];
-            for x in 0..5 {
-                bingo_spaces[y][x].value = row[x];
-            }
-        }
+/// Boards are equal when their spaces and results are; [BingoBoard::pattern_lines] only caches what the
+/// marked spaces already say
+impl<T: PartialEq> PartialEq for BingoBoard<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.spaces == other.spaces
+            && self.width == other.width
+            && self.height == other.height
+            && self.marked_count == other.marked_count
+            && self.row_marks == other.row_marks
+            && self.col_marks == other.col_marks
+            && self.is_winner == other.is_winner
+            && self.winning_val == other.winning_val
+            && self.winning_pattern == other.winning_pattern
+            && self.placement == other.placement
+    }
+}
+
+impl<T: Eq> Eq for BingoBoard<T> {}
+
+impl<T: Cell> BingoBoard<T> {
+    /// Creates a board from rows of values; the board's dimensions are taken from the rows given
+    pub fn new(values: &[Vec<T>]) -> Self {
+        let spaces: Vec<Vec<BingoSpace<T>>> = values
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| BingoSpace {
+                        value: value.clone(),
+                        marked: false,
+                    })
+                    .collect()
+            })
+            .collect();
+        let height = spaces.len();
+        let width = spaces.first().map_or(0, |row| row.len());
 
         Self {
-            spaces: bingo_spaces,
+            spaces,
+            width,
+            height,
             marked_count: 0,
+            row_marks: vec![0; height],
+            col_marks: vec![0; width],
             is_winner: false,
             winning_val: None,
+            winning_pattern: None,
             placement: None,
+            pattern_lines: None,
         }
     }
 
     fn column_is_winner(&self, col_idx: usize) -> bool {
-        let mut marked_count = 0;
-        for y in 0..5 {
-            let space = &self.spaces[y][col_idx];
-            if space.marked {
-                marked_count += 1;
-            }
+        self.col_marks[col_idx] as usize == self.height
+    }
+
+    fn row_is_winner(&self, row_idx: usize) -> bool {
+        self.row_marks[row_idx] as usize == self.width
+    }
+
+    /// Marks a single space; returning whether it was previously unmarked
+    fn mark_space(&mut self, row_idx: usize, col_idx: usize) -> bool {
+        let space = &mut self.spaces[row_idx][col_idx];
+        if space.marked {
+            return false;
         }
 
-        marked_count == 5
+        space.marked = true;
+        self.marked_count += 1;
+        self.row_marks[row_idx] += 1;
+        self.col_marks[col_idx] += 1;
+        if let Some(pattern_lines) = &mut self.pattern_lines {
+            pattern_lines.mark(row_idx, col_idx);
+        }
+        true
     }
 
-    /// Updates the [BingoBoard]'s state to match whether it is a winner
-    pub fn determine_if_winner(&mut self, value: u32, placement: &mut usize) {
-        if self.is_winner {
-            return;
+    /// Checks whether marking the space at `(row_idx, col_idx)` completed one of the lines of the pattern
+    /// at `pattern_idx` in [BingoBoard::pattern_lines].
+    ///
+    /// Only lines passing through that space are considered, since any other completed line would already
+    /// have been detected when its last space was marked.
+    fn pattern_completed_at(
+        &self,
+        pattern_idx: usize,
+        pattern: &WinPattern,
+        row_idx: usize,
+        col_idx: usize,
+    ) -> bool {
+        match pattern {
+            WinPattern::Rows => self.row_is_winner(row_idx),
+            WinPattern::Columns => self.column_is_winner(col_idx),
+            _ => self
+                .pattern_lines
+                .as_ref()
+                .is_some_and(|lines| lines.completed_at(pattern_idx, row_idx, col_idx)),
         }
+    }
 
-        if self.marked_count < 5 {
-            self.is_winner = false;
+    /// Updates the [BingoBoard]'s state to match whether it is a winner after marking `marked_spaces`.
+    ///
+    /// The board wins with the first of `patterns` that it has completed.
+    pub fn determine_if_winner(
+        &mut self,
+        value: T,
+        placement: &mut usize,
+        patterns: &[WinPattern],
+        marked_spaces: &[(usize, usize)],
+    ) {
+        if self.is_winner {
             return;
         }
 
-        for y in 0..self.spaces.len() {
-            let mut marked_count = 0;
-            let row = &self.spaces[y];
-            for x in 0..row.len() {
-                if y == 0 {
-                    // Make sure to check columns only for the first row
-                    if self.column_is_winner(x) {
-                        self.is_winner = true;
-                        self.winning_val = Some(value);
-                        self.placement = Some(*placement);
-                        *placement += 1;
-                        return;
-                    }
-                }
-                let space = &self.spaces[y][x];
-                if space.marked {
-                    marked_count += 1;
-                }
-            }
-            if marked_count == 5 {
-                self.is_winner = true;
-                self.winning_val = Some(value);
-                self.placement = Some(*placement);
-                *placement += 1;
-                return;
-            }
+        let is_stale = self
+            .pattern_lines
+            .as_ref()
+            .is_none_or(|lines| lines.patterns() != patterns);
+        if is_stale {
+            let spaces = &self.spaces;
+            self.pattern_lines = Some(PatternLines::new(
+                patterns,
+                self.width,
+                self.height,
+                |y, x| spaces[y][x].marked,
+            ));
         }
+        let completed = patterns.iter().enumerate().find(|&(pattern_idx, pattern)| {
+            marked_spaces
+                .iter()
+                .any(|&(y, x)| self.pattern_completed_at(pattern_idx, pattern, y, x))
+        });
+        if let Some((_, pattern)) = completed {
+            self.is_winner = true;
+            self.winning_val = Some(value);
+            self.winning_pattern = Some(pattern.clone());
+            self.placement = Some(*placement);
+            *placement += 1;
+        }
+    }
+
+    /// Marks every space holding `value`, if any, and checks whether the board has won
+    pub fn mark_if_present(&mut self, value: T, placement: &mut usize, patterns: &[WinPattern]) {
+        let spaces: Vec<(usize, usize)> = self
+            .spaces
+            .iter()
+            .enumerate()
+            .flat_map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter(|(_, space)| space.value == value)
+                    .map(move |(x, _)| (y, x))
+            })
+            .collect();
+        self.mark_spaces(value, spaces, placement, patterns);
+    }
+
+    /// Marks the given spaces, which must hold the drawn `value`, and checks whether the board has won.
+    ///
+    /// Unlike [BingoBoard::mark_if_present], this does not search the board, so it only costs as much as
+    /// the spaces given. Returns the spaces that were not already marked.
+    pub fn mark_spaces(
+        &mut self,
+        value: T,
+        spaces: impl IntoIterator<Item = (usize, usize)>,
+        placement: &mut usize,
+        patterns: &[WinPattern],
+    ) -> Vec<(usize, usize)> {
+        let newly_marked: Vec<(usize, usize)> = spaces
+            .into_iter()
+            .filter(|&(y, x)| self.mark_space(y, x))
+            .collect();
+        self.determine_if_winner(value, placement, patterns, &newly_marked);
+        newly_marked
+    }
 
+    /// Clears every mark and the board's result, as if nothing had been drawn yet
+    pub fn reset(&mut self) {
+        for space in self.spaces.iter_mut().flatten() {
+            space.marked = false;
+        }
+        self.marked_count = 0;
+        self.row_marks.fill(0);
+        self.col_marks.fill(0);
         self.is_winner = false;
+        self.winning_val = None;
+        self.winning_pattern = None;
+        self.placement = None;
+        if let Some(pattern_lines) = &mut self.pattern_lines {
+            pattern_lines.clear_marks();
+        }
+    }
+
+    /// The first line of the board's winning pattern that is fully marked, if the board has won
+    pub fn winning_line(&self) -> Option<WinLine> {
+        self.winning_pattern
+            .as_ref()?
+            .lines(self.width, self.height)
+            .into_iter()
+            .find(|line| line.iter().all(|&(y, x)| self.spaces[y][x].marked))
+    }
+
+    /// The board's values, row by row
+    pub fn values(&self) -> Vec<Vec<T>> {
+        self.spaces
+            .iter()
+            .map(|row| row.iter().map(|space| space.value.clone()).collect())
+            .collect()
     }
 
+    /// Scores the board with a game's own rules
+    pub fn score_with(&self, scorer: &(impl Scorer<T> + ?Sized)) -> u32 {
+        scorer.score(self)
+    }
+}
+
+impl BingoBoard<u32> {
     pub fn sum_of_unmarked(&self) -> u32 {
         self.spaces
             .iter()
@@ -101,117 +291,711 @@ impl BingoBoard {
             .filter(|&space| !space.marked)
             .fold(0, |acc, space| acc + space.value)
     }
+}
 
-    pub fn mark_if_present(&mut self, value: u32, placement: &mut usize) {
-        self.spaces
-            .iter_mut()
-            .flatten()
-            .filter(|space| space.value == value)
-            .for_each(|space| {
-                space.marked = true;
-                self.marked_count += 1;
-            });
-        self.determine_if_winner(value, placement);
-    }
+/// Runs all of the Bingo games; determining winners, the order in which they won, and the associated winning values
+///
+/// Boards win by completing any of the given `patterns`; the pattern each board completed is recorded in
+/// [BingoBoard::winning_pattern].
+fn run_game<T: Cell>(
+    vals: &[T],
+    boards: &mut [BingoBoard<T>],
+    patterns: &[WinPattern],
+) -> (Option<BingoBoard<T>>, Option<BingoBoard<T>>) {
+    play(vals, boards, patterns, None);
 
-    pub fn calculate_score(&self) -> u32 {
-        self.sum_of_unmarked() * self.winning_val.unwrap_or(0)
-    }
+    find_winners(boards)
 }
 
-fn load_input_data(input: &str) -> (Vec<u32>, Vec<BingoBoard>) {
-    let mut lines = input.lines();
-    // Load values from first line
-    let values_line = lines.next().unwrap();
-    let values: Vec<u32> = values_line
-        .split(",")
-        .map(|val_str| val_str.parse().unwrap())
-        .collect();
-
-    // Load BingoBoards from remaining lines
-    let board_inputs: Vec<Vec<u32>> = lines
-        .filter(|&line| !line.is_empty())
-        .map(|line| {
-            line.split_whitespace()
-                .map(|val| val.parse::<u32>().unwrap())
-                .collect()
-        })
-        .collect();
+/// Plays every draw on the boards; recording what happened in `events` if given, with each winner scored by
+/// the scorer given alongside them
+fn play<T: Cell>(
+    vals: &[T],
+    boards: &mut [BingoBoard<T>],
+    patterns: &[WinPattern],
+    events: Option<(&mut Vec<GameEvent<T>>, &dyn Scorer<T>)>,
+) {
+    play_with_index(vals, boards, &BingoIndex::new(boards), patterns, events);
+}
 
-    let mut boards = vec![];
-    for i in (0..board_inputs.len()).step_by(5) {
-        let stop = i + 5;
-        let input_rows = &board_inputs[i..stop];
-        boards.push(BingoBoard::new(input_rows));
+/// Plays every draw like [play], finding the spaces to mark in `index`, which must have been built from
+/// `boards`
+fn play_with_index<T: Cell>(
+    vals: &[T],
+    boards: &mut [BingoBoard<T>],
+    index: &BingoIndex<T>,
+    patterns: &[WinPattern],
+    mut events: Option<(&mut Vec<GameEvent<T>>, &dyn Scorer<T>)>,
+) {
+    if let Some((events, _)) = events.as_mut() {
+        events.extend(
+            boards
+                .iter()
+                .enumerate()
+                .map(|(board, bingo_board)| GameEvent::Board {
+                    board,
+                    values: bingo_board.values(),
+                }),
+        );
     }
 
-    (values, boards)
+    let mut placement = 0;
+    for (turn, val) in vals.iter().enumerate() {
+        if let Some((events, _)) = events.as_mut() {
+            events.push(GameEvent::Draw {
+                turn,
+                value: val.clone(),
+            });
+        }
+
+        let mut marked_any = false;
+        // Spaces are indexed in board order, so boards still win in the same order as a full scan
+        for spaces in index.spaces_by_board(val) {
+            let board_idx = spaces[0].0;
+            let board = &mut boards[board_idx];
+            if board.is_winner {
+                continue;
+            }
+
+            let spaces = spaces.iter().map(|&(_, y, x)| (y, x));
+            let newly_marked = board.mark_spaces(val.clone(), spaces, &mut placement, patterns);
+            marked_any |= !newly_marked.is_empty();
+
+            if let Some((events, scorer)) = events.as_mut() {
+                events.extend(newly_marked.iter().map(|&(row, col)| GameEvent::Mark {
+                    turn,
+                    board: board_idx,
+                    row,
+                    col,
+                }));
+                if board.is_winner {
+                    events.push(GameEvent::Win {
+                        turn,
+                        board: board_idx,
+                        value: val.clone(),
+                        pattern: board.winning_pattern.clone().unwrap(),
+                        placement: board.placement.unwrap(),
+                        score: board.score_with(*scorer),
+                    });
+                }
+            }
+        }
+
+        if !marked_any {
+            if let Some((events, _)) = events.as_mut() {
+                events.push(GameEvent::Miss {
+                    turn,
+                    value: val.clone(),
+                });
+            }
+        }
+    }
 }
 
-/// Runs all of the Bingo games; determining winners, the order in which they won, and the associated winning values
-fn run_game(vals: &[u32], boards: &mut [BingoBoard]) -> (Option<BingoBoard>, Option<BingoBoard>) {
+/// Runs the games like [run_game], but marks boards by scanning every space of every board for each draw
+fn run_game_scan<T: Cell>(
+    vals: &[T],
+    boards: &mut [BingoBoard<T>],
+    patterns: &[WinPattern],
+) -> (Option<BingoBoard<T>>, Option<BingoBoard<T>>) {
     let mut placement = 0;
-    for i in 0..vals.len() {
-        let val = vals[i];
-        for board_idx in 0..boards.len() {
-            let board = &mut boards[board_idx];
+    for val in vals {
+        for board in boards.iter_mut() {
             if !board.is_winner {
-                board.mark_if_present(val, &mut placement);
+                board.mark_if_present(val.clone(), &mut placement, patterns);
             }
         }
     }
 
-    let first_winner = boards.iter().find(|&board| {
-        return match board.placement {
-            Some(placement) => placement == 0,
-            None => false,
-        };
-    });
+    find_winners(boards)
+}
 
-    let first_winner = match first_winner {
-        Some(board) => Some(board.clone()),
-        None => None,
-    };
+/// Finds the first and last boards to win
+fn find_winners<T: Cell>(
+    boards: &[BingoBoard<T>],
+) -> (Option<BingoBoard<T>>, Option<BingoBoard<T>>) {
+    let first_winner = boards
+        .iter()
+        .find(|&board| board.placement == Some(0))
+        .cloned();
 
     let last_winner = boards
         .iter()
         .filter(|&board| board.is_winner && board.placement.is_some())
-        .max_by(|&x, &y| x.placement.cmp(&y.placement));
+        .max_by(|&x, &y| x.placement.cmp(&y.placement))
+        .cloned();
+
+    (first_winner, last_winner)
+}
 
-    let last_winner = match last_winner {
-        Some(board) => Some(board.clone()),
-        None => None,
+/// Parses a board size given as `WIDTHxHEIGHT`, or a single number for square boards
+fn parse_board_size(size: &str) -> Result<(usize, usize), String> {
+    let parse_dimension = |dimension: &str| match dimension.trim().parse::<usize>() {
+        Ok(0) => Err("board dimensions must be greater than 0".to_string()),
+        Ok(dimension) => Ok(dimension),
+        Err(err) => Err(format!("invalid board dimension '{dimension}': {err}")),
     };
 
-    (first_winner, last_winner)
+    match size.split_once(['x', 'X']) {
+        Some((width, height)) => Ok((parse_dimension(width)?, parse_dimension(height)?)),
+        None => {
+            let side = parse_dimension(size)?;
+            Ok((side, side))
+        }
+    }
+}
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Path to an input file
+    #[arg(short, long, value_name = "input", default_value = "input.txt")]
+    input_path: PathBuf,
+
+    /// Size of every board as `WIDTHxHEIGHT` (or `N` for NxN); inferred from blank-line separated boards if omitted
+    #[arg(short, long, value_parser = parse_board_size)]
+    size: Option<(usize, usize)>,
+
+    /// Winning patterns: rows, columns, diagonals, corners, x or full-card (comma separated)
+    #[arg(short, long, value_delimiter = ',', default_values_t = WinPattern::DEFAULT)]
+    pattern: Vec<WinPattern>,
+
+    /// File of custom pattern masks to win with, in addition to `--pattern`
+    #[arg(long, value_name = "PATH")]
+    pattern_file: Option<PathBuf>,
+
+    /// Mark boards by scanning every space for each draw instead of using the value index
+    #[arg(long, default_value = "false")]
+    scan: bool,
+
+    /// Compute each board's winning turn directly from the draw order instead of simulating the game
+    #[arg(long, default_value = "false", conflicts_with = "scan")]
+    analytical: bool,
+
+    /// Write every draw, mark and win to a JSON Lines file (`-` for stdout, which then holds only the log)
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["scan", "analytical"])]
+    event_log: Option<PathBuf>,
+
+    /// Draw every winning board, in the order they won, with its winning line emphasised
+    #[arg(long, default_value = "false", conflicts_with = "analytical")]
+    show_boards: bool,
+
+    /// When to highlight boards with ANSI colours instead of brackets
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Width to wrap boards to; defaults to the `COLUMNS` environment variable or 80
+    #[arg(long)]
+    width: Option<usize>,
+
+    /// Read draws and spaces as whitespace-free tokens, such as words or emoji, instead of numbers
+    #[arg(long, default_value = "false", conflicts_with_all = ["analytical", "event_log", "show_boards"])]
+    tokens: bool,
+
+    /// How to score the winning boards
+    #[arg(long, value_enum, default_value_t = ScoreRule::Default, conflicts_with = "analytical")]
+    score: ScoreRule,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ScoreRule {
+    /// The puzzle's score for numbers, or the number of unmarked spaces for tokens
+    Default,
+    /// The sum of the unmarked spaces times the winning value; numbers only
+    Final,
+    /// The sum of the unmarked spaces; numbers only
+    UnmarkedSum,
+    /// The number of unmarked spaces
+    Unmarked,
+    /// The number of marked spaces
+    Marked,
+}
+
+impl ScoreRule {
+    /// The [Scorer] for a numeric game
+    fn numeric_scorer(self) -> fn(&BingoBoard) -> u32 {
+        match self {
+            ScoreRule::Default | ScoreRule::Final => final_score,
+            ScoreRule::UnmarkedSum => unmarked_sum,
+            ScoreRule::Unmarked => unmarked_count,
+            ScoreRule::Marked => marked_count,
+        }
+    }
+
+    /// The [Scorer] for a game of tokens, which cannot be summed
+    fn token_scorer(self) -> anyhow::Result<fn(&BingoBoard<String>) -> u32> {
+        match self {
+            ScoreRule::Default | ScoreRule::Unmarked => Ok(unmarked_count),
+            ScoreRule::Marked => Ok(marked_count),
+            ScoreRule::Final | ScoreRule::UnmarkedSum => {
+                anyhow::bail!("Token games can only be scored by counting spaces")
+            }
+        }
+    }
+}
+
+impl Args {
+    fn render_style(&self) -> RenderStyle {
+        let use_ansi = match self.color {
+            ColorChoice::Auto => io::stdout().is_terminal(),
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        };
+        if use_ansi {
+            RenderStyle::Ansi
+        } else {
+            RenderStyle::Plain
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Rebuild the boards from an event log and show their state
+    Replay {
+        /// Event log written with `--event-log`
+        log_path: PathBuf,
+
+        /// Show the boards as of the end of this turn (0-based) instead of the end of the game
+        #[arg(short, long)]
+        turn: Option<usize>,
+    },
+    /// Play the game and list every board's result, with boards that never won at the end
+    Rank {
+        /// Column to sort by
+        #[arg(short, long, value_enum, default_value_t = RankSort::Placement)]
+        sort: RankSort,
+
+        /// Sort in descending order
+        #[arg(short, long, default_value = "false")]
+        reverse: bool,
+
+        /// Format of the table
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+    /// Write a random input file from a seed
+    Generate {
+        /// Number of boards
+        #[arg(short, long, default_value = "100")]
+        boards: usize,
+
+        /// Size of every board as `WIDTHxHEIGHT` (or `N` for NxN)
+        #[arg(short, long, value_parser = parse_board_size, default_value = "5")]
+        size: (usize, usize),
+
+        /// Values to fill boards and draws with, as `MIN-MAX`
+        #[arg(short, long, value_parser = parse_value_range, default_value = "0-99")]
+        range: RangeInclusive<u32>,
+
+        /// Number of values to draw; defaults to every value in the range
+        #[arg(short, long)]
+        draws: Option<usize>,
+
+        #[arg(long, default_value = "0")]
+        seed: u64,
+
+        /// Board (0-based) that must win first with the selected patterns
+        #[arg(long, value_name = "BOARD")]
+        first_winner: Option<usize>,
+
+        /// File to write the input to (stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Save a new game, with nothing drawn yet, so it can be played over several sessions
+    Start {
+        /// File to save the game to
+        #[arg(long, default_value = "bingo-state.json")]
+        state: PathBuf,
+    },
+    /// Draw the next values of a saved game
+    Draw {
+        /// Number of values to draw
+        #[arg(default_value = "1")]
+        count: usize,
+
+        /// File the game is saved in
+        #[arg(long, default_value = "bingo-state.json")]
+        state: PathBuf,
+    },
+    /// Show the progress of a saved game
+    Status {
+        /// File the game is saved in
+        #[arg(long, default_value = "bingo-state.json")]
+        state: PathBuf,
+    },
+    /// Estimate each board's chance of winning first and last over random draw orders
+    Simulate {
+        /// Number of games to play
+        #[arg(short = 'n', long, default_value = "10000")]
+        trials: usize,
+
+        /// Seed for the random draw orders
+        #[arg(long, default_value = "0")]
+        seed: u64,
+
+        /// Values that can be drawn, as `MIN-MAX`; defaults to the distinct values in the input's draws
+        #[arg(long, value_parser = parse_value_range)]
+        pool: Option<RangeInclusive<u32>>,
+
+        #[arg(short, long)]
+        jobs: Option<usize>,
+
+        /// Format of the estimates
+        #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+        format: OutputFormat,
+    },
+}
+
+/// Parses an inclusive range of values given as `MIN-MAX`
+fn parse_value_range(range: &str) -> Result<RangeInclusive<u32>, String> {
+    let (min, max) = range
+        .split_once('-')
+        .ok_or_else(|| format!("invalid range '{range}'; expected MIN-MAX"))?;
+    let parse_bound = |bound: &str| {
+        bound
+            .trim()
+            .parse::<u32>()
+            .map_err(|err| format!("invalid range bound '{bound}': {err}"))
+    };
+    let (min, max) = (parse_bound(min)?, parse_bound(max)?);
+    if min > max {
+        return Err(format!(
+            "range minimum {min} is greater than its maximum {max}"
+        ));
+    }
+
+    Ok(min..=max)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+/// Prints the state of every board rebuilt from an event log
+fn print_replay(log_path: &Path, turn: Option<usize>, style: RenderStyle) -> anyhow::Result<()> {
+    let events = read_jsonl(BufReader::new(File::open(log_path)?))?;
+    let Replay { boards, scores } = replay(&events, turn)?;
+    let renderer = BoardRenderer::new(style, &boards);
+
+    for (board_idx, (board, score)) in boards.iter().zip(scores).enumerate() {
+        if let (true, Some(score)) = (board.is_winner, score) {
+            print_win(board_idx, board, score);
+        } else {
+            println!(
+                "Board {board_idx}: {} of {} spaces marked",
+                board.marked_count,
+                board.width * board.height
+            );
+        }
+        for row in renderer.render_board(board) {
+            println!("  {row}");
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Prints how far a saved game has got and the boards that have won so far, scored with `scorer`
+fn print_status(state: &GameState, scorer: &impl Scorer<u32>) {
+    println!(
+        "Drawn {} of {} values; {} of {} boards have won",
+        state.cursor,
+        state.draws.len(),
+        state.placement,
+        state.boards.len()
+    );
+    if let Some(last_drawn) = state.cursor.checked_sub(1) {
+        println!("Last drawn: {}", state.draws[last_drawn]);
+    }
+
+    let mut winners: Vec<(usize, &BingoBoard)> = state
+        .boards
+        .iter()
+        .enumerate()
+        .filter(|(_, board)| board.is_winner)
+        .collect();
+    winners.sort_by_key(|(_, board)| board.placement);
+    for (board_idx, board) in winners {
+        print_win(board_idx, board, board.score_with(scorer));
+    }
+}
+
+/// Prints how a winning board won and the score it won with
+fn print_win(board_idx: usize, board: &BingoBoard, score: u32) {
+    println!(
+        "Board {board_idx}: won #{} with a {} on {}, score {score}",
+        board.placement.unwrap_or(0) + 1,
+        board.winning_pattern.as_ref().unwrap(),
+        board.winning_val.unwrap_or(0),
+    );
 }
 
 fn main() -> anyhow::Result<()> {
-    let input_str = fs::read_to_string("input.txt")?;
-    let (values, mut bingo_boards) = load_input_data(&input_str);
+    let args = Args::parse();
+
+    match &args.command {
+        Some(Command::Replay { log_path, turn }) => {
+            return print_replay(log_path, *turn, args.render_style());
+        }
+        Some(Command::Draw { count, state }) => {
+            let mut game = GameState::load(state)?;
+            if game.remaining().is_empty() {
+                println!("Every value has already been drawn");
+                return Ok(());
+            }
+            let start = game.cursor;
+            let winners = game.draw(*count);
+            let drawn: Vec<String> = game.draws[start..game.cursor]
+                .iter()
+                .map(|val| val.to_string())
+                .collect();
+            println!("Drew {}", drawn.join(", "));
+            for board_idx in winners {
+                let board = &game.boards[board_idx];
+                print_win(
+                    board_idx,
+                    board,
+                    board.score_with(&args.score.numeric_scorer()),
+                );
+            }
+            game.save(state)?;
+            return Ok(());
+        }
+        Some(Command::Status { state }) => {
+            print_status(&GameState::load(state)?, &args.score.numeric_scorer());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let style = args.render_style();
+    let mut patterns = args.pattern;
+    if let Some(pattern_file) = args.pattern_file {
+        patterns.extend(load_custom_patterns(&fs::read_to_string(pattern_file)?)?);
+    }
+
+    if let Some(Command::Generate {
+        boards,
+        size: (width, height),
+        range,
+        draws,
+        seed,
+        first_winner,
+        output,
+    }) = args.command
+    {
+        let config = ScenarioConfig {
+            board_count: boards,
+            width,
+            height,
+            values: range,
+            draw_count: draws,
+            seed,
+            first_winner,
+        };
+        let input = generate(&config, &patterns)?.to_input();
+        match output {
+            Some(output) => fs::write(output, input)?,
+            None => print!("{input}"),
+        }
+        return Ok(());
+    }
+
+    let input_str = fs::read_to_string(&args.input_path)?;
+    if args.tokens {
+        if args.command.is_some() {
+            anyhow::bail!("Subcommands only support numeric games; drop `--tokens`");
+        }
+        let scorer = args.score.token_scorer()?;
+        let (values, mut bingo_boards) = load_token_input(&input_str, args.size)?;
+        let (first_winner, last_winner) = if args.scan {
+            run_game_scan(&values, &mut bingo_boards, &patterns)
+        } else {
+            run_game(&values, &mut bingo_boards, &patterns)
+        };
+        print_winners(first_winner, last_winner, &scorer);
+        return Ok(());
+    }
+    let (values, mut bingo_boards) = load_input_data(&input_str, args.size)?;
     drop(input_str);
+    for warning in find_duplicates(&values, &bingo_boards) {
+        eprintln!("warning: {warning}");
+    }
 
-    let (first_winner, last_winner) = run_game(&values, &mut bingo_boards);
+    if let Some(Command::Start { state }) = &args.command {
+        let game = GameState::new(values, bingo_boards, patterns);
+        game.save(state)?;
+        println!(
+            "Started a game with {} boards and {} draws in {}",
+            game.boards.len(),
+            game.draws.len(),
+            state.display()
+        );
+        return Ok(());
+    }
 
-    match first_winner {
-        Some(board) => {
-            let score = board.calculate_score();
-            println!("First winner's score: {score}");
+    if let Some(Command::Simulate {
+        trials,
+        seed,
+        pool,
+        jobs,
+        format,
+    }) = args.command
+    {
+        let pool: Vec<u32> = match pool {
+            Some(pool) => pool.collect(),
+            None => {
+                let mut pool = values.clone();
+                pool.sort();
+                pool.dedup();
+                pool
+            }
+        };
+        let available_threads = thread::available_parallelism()?.get();
+        let jobs = match jobs {
+            Some(jobs) if jobs > 0 && jobs <= available_threads => jobs,
+            _ => available_threads,
+        };
+
+        let odds = simulate(&bingo_boards, &patterns, &pool, trials, seed, jobs);
+        match format {
+            OutputFormat::Text => print!("{}", odds.to_text()),
+            OutputFormat::Csv => print!("{}", odds.to_csv()),
+            OutputFormat::Json => println!("{}", odds.to_json()?),
         }
-        None => {}
+        return Ok(());
     }
 
-    match last_winner {
-        Some(board) => {
-            let score = board.calculate_score();
-            println!("Last winner's score: {score}");
+    if args.analytical {
+        if matches!(args.command, Some(Command::Rank { .. })) {
+            anyhow::bail!("The rank table is built from a simulated game; drop `--analytical`");
+        }
+        let results = solve(&values, &bingo_boards, &patterns);
+        let first_winner = results.iter().find(|result| result.placement == Some(0));
+        let last_winner = results
+            .iter()
+            .filter(|result| result.placement.is_some())
+            .max_by_key(|result| result.placement);
+
+        if let Some(result) = first_winner {
+            let score = result.score.unwrap();
+            let pattern = result.winning_pattern.as_ref().unwrap();
+            println!("First winner's score: {score} ({pattern})");
         }
-        None => {}
+
+        if let Some(result) = last_winner {
+            let score = result.score.unwrap();
+            let pattern = result.winning_pattern.as_ref().unwrap();
+            println!("Last winner's score: {score} ({pattern})");
+        }
+
+        return Ok(());
     }
 
+    let log_to_stdout = args.event_log.as_deref() == Some(Path::new("-"));
+    if log_to_stdout && (args.show_boards || matches!(args.command, Some(Command::Rank { .. }))) {
+        anyhow::bail!(
+            "`--event-log -` keeps stdout for the log; write the log to a file to also show boards or ranks"
+        );
+    }
+
+    let scorer = args.score.numeric_scorer();
+    let (first_winner, last_winner) = if args.scan {
+        run_game_scan(&values, &mut bingo_boards, &patterns)
+    } else if let Some(event_log) = &args.event_log {
+        let mut events = vec![];
+        play(
+            &values,
+            &mut bingo_boards,
+            &patterns,
+            Some((&mut events, &scorer)),
+        );
+        if log_to_stdout {
+            write_jsonl(&events, io::stdout().lock())?;
+            // Keep stdout as valid JSON Lines
+            return Ok(());
+        }
+        write_jsonl(&events, BufWriter::new(File::create(event_log)?))?;
+        find_winners(&bingo_boards)
+    } else {
+        run_game(&values, &mut bingo_boards, &patterns)
+    };
+
+    if let Some(Command::Rank {
+        sort,
+        reverse,
+        format,
+    }) = args.command
+    {
+        let mut table = RankTable::new(&values, &bingo_boards, &scorer);
+        table.sort(sort, reverse);
+        match format {
+            OutputFormat::Text => print!("{}", table.to_text()),
+            OutputFormat::Csv => print!("{}", table.to_csv()),
+            OutputFormat::Json => println!("{}", table.to_json()?),
+        }
+        return Ok(());
+    }
+
+    if args.show_boards {
+        let mut winners: Vec<(usize, &BingoBoard)> = bingo_boards
+            .iter()
+            .enumerate()
+            .filter(|(_, board)| board.is_winner)
+            .collect();
+        winners.sort_by_key(|(_, board)| board.placement);
+        let titled: Vec<(String, &BingoBoard)> = winners
+            .into_iter()
+            .map(|(board_idx, board)| {
+                let title = format!(
+                    "#{} board {board_idx} ({})",
+                    board.placement.unwrap_or(0) + 1,
+                    board.winning_pattern.as_ref().unwrap()
+                );
+                (title, board)
+            })
+            .collect();
+        let renderer = BoardRenderer::new(style, titled.iter().map(|(_, board)| *board));
+        print!(
+            "{}",
+            renderer.render_boards(&titled, args.width.unwrap_or_else(terminal_width))
+        );
+        println!();
+    }
+
+    print_winners(first_winner, last_winner, &scorer);
+
     Ok(())
 }
 
+/// Prints the scores of the first and last boards to win
+fn print_winners<T: Cell>(
+    first_winner: Option<BingoBoard<T>>,
+    last_winner: Option<BingoBoard<T>>,
+    scorer: &impl Scorer<T>,
+) {
+    if let Some(board) = first_winner {
+        let score = board.score_with(scorer);
+        let pattern = board.winning_pattern.unwrap();
+        println!("First winner's score: {score} ({pattern})");
+    }
+
+    if let Some(board) = last_winner {
+        let score = board.score_with(scorer);
+        let pattern = board.winning_pattern.unwrap();
+        println!("Last winner's score: {score} ({pattern})");
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -225,7 +1009,7 @@ mod test {
         }
     }
 
-    const TEST_INPUT_DATA: &'static str =
+    const TEST_INPUT_DATA: &str =
         "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1\n\
         \n\
         22 13 17 11  0\n\
@@ -262,47 +1046,20 @@ mod test {
             vec![1, 12, 20, 15, 19],
         ];
         static ref BINGO_BOARD: BingoBoard = BingoBoard {
-            spaces: [
-                [
-                    BingoSpace::new(22),
-                    BingoSpace::new(13),
-                    BingoSpace::new(17),
-                    BingoSpace::new(11),
-                    BingoSpace::new(0),
-                ],
-                [
-                    BingoSpace::new(8),
-                    BingoSpace::new(2),
-                    BingoSpace::new(23),
-                    BingoSpace::new(4),
-                    BingoSpace::new(24),
-                ],
-                [
-                    BingoSpace::new(21),
-                    BingoSpace::new(9),
-                    BingoSpace::new(14),
-                    BingoSpace::new(16),
-                    BingoSpace::new(7),
-                ],
-                [
-                    BingoSpace::new(6),
-                    BingoSpace::new(10),
-                    BingoSpace::new(3),
-                    BingoSpace::new(18),
-                    BingoSpace::new(5),
-                ],
-                [
-                    BingoSpace::new(1),
-                    BingoSpace::new(12),
-                    BingoSpace::new(20),
-                    BingoSpace::new(15),
-                    BingoSpace::new(19),
-                ],
-            ],
+            spaces: BINGO_VAL_ARRAY
+                .iter()
+                .map(|row| row.iter().map(|&value| BingoSpace::new(value)).collect())
+                .collect(),
+            width: 5,
+            height: 5,
             is_winner: false,
             marked_count: 0,
+            row_marks: vec![0; 5],
+            col_marks: vec![0; 5],
             winning_val: None,
-            placement: None
+            winning_pattern: None,
+            placement: None,
+            pattern_lines: None
         };
     }
 
@@ -317,11 +1074,11 @@ mod test {
         let marked_col = 3;
         // Mark all spaces in column 3
         for y in 0..5 {
-            test_board.spaces[y][marked_col].marked = true;
+            test_board.mark_space(y, marked_col);
         }
 
-        assert_eq!(test_board.column_is_winner(2), false);
-        assert_eq!(test_board.column_is_winner(marked_col), true);
+        assert!(!test_board.column_is_winner(2));
+        assert!(test_board.column_is_winner(marked_col));
     }
 
     #[test]
@@ -341,7 +1098,7 @@ mod test {
     fn test_load_input_data() {
         assert_eq!(TEST_INPUT_DATA.lines().count(), TEST_INPUT_LINE_COUNT);
 
-        let (values, bingo_boards) = load_input_data(TEST_INPUT_DATA);
+        let (values, bingo_boards) = load_input_data(TEST_INPUT_DATA, None).unwrap();
         assert_eq!(values.as_slice(), TEST_VALUES);
 
         assert_eq!(bingo_boards.len(), 3);
@@ -349,12 +1106,232 @@ mod test {
 
     #[test]
     fn test_run_game() {
-        let (values, mut bingo_boards) = load_input_data(TEST_INPUT_DATA);
-        let (first_winner, last_winner) = run_game(&values, &mut bingo_boards);
+        let (values, mut bingo_boards) = load_input_data(TEST_INPUT_DATA, None).unwrap();
+        let (first_winner, last_winner) =
+            run_game(&values, &mut bingo_boards, &WinPattern::DEFAULT);
 
         assert!(first_winner.is_some());
         // assert_eq!(first_winner, Some(expected_first_winner));
         assert!(last_winner.is_some());
         // assert_eq!(last_winner, Some(expected_last_winner));
+        assert_eq!(first_winner.unwrap().score_with(&final_score), 4512);
+        assert_eq!(last_winner.unwrap().score_with(&final_score), 1924);
+    }
+
+    #[test]
+    fn test_load_input_data_inferred_size() {
+        let input = "1,2,3\n\n1 2 3\n4 5 6\n\n7 8 9\n10 11 12\n";
+        let (_, bingo_boards) = load_input_data(input, None).unwrap();
+
+        assert_eq!(bingo_boards.len(), 2);
+        assert_eq!((bingo_boards[0].width, bingo_boards[0].height), (3, 2));
+        assert_eq!((bingo_boards[1].width, bingo_boards[1].height), (3, 2));
+    }
+
+    #[test]
+    fn test_load_input_data_explicit_size() {
+        let input = "1,2,3\n1 2 3 4 5 6\n7 8 9\n10 11 12 13 14 15 16 17 18\n";
+        let (_, bingo_boards) = load_input_data(input, Some((3, 3))).unwrap();
+
+        assert_eq!(bingo_boards.len(), 2);
+        assert_eq!(bingo_boards[1].spaces[0][0].value, 10);
+        assert_eq!(bingo_boards[1].spaces[2][2].value, 18);
+    }
+
+    #[test]
+    fn test_run_game_rectangular() {
+        let input = "5,1,9,2,6\n\n1 2 3 4\n5 6 7 8\n\n9 1 2 0\n5 6 3 4\n";
+        let (values, mut bingo_boards) = load_input_data(input, None).unwrap();
+        let (first_winner, last_winner) =
+            run_game(&values, &mut bingo_boards, &WinPattern::DEFAULT);
+
+        // Column `1 5` completes on the second draw and column `9 5` on the third
+        let first_winner = first_winner.unwrap();
+        assert_eq!(first_winner.winning_val, Some(1));
+        assert_eq!(first_winner.score_with(&final_score), 2 + 3 + 4 + 6 + 7 + 8);
+        let last_winner = last_winner.unwrap();
+        assert_eq!(last_winner.winning_val, Some(9));
+        assert_eq!(last_winner.placement, Some(1));
+    }
+
+    #[test]
+    fn test_run_game_patterns() {
+        let input = "1,5,9,3,7\n\n1 2 3\n4 5 6\n7 8 9\n\n1 4 3\n6 2 8\n7 8 9\n";
+        let patterns = [
+            WinPattern::Rows,
+            WinPattern::Diagonals,
+            WinPattern::FourCorners,
+        ];
+        let (values, mut bingo_boards) = load_input_data(input, None).unwrap();
+        let (first_winner, last_winner) = run_game(&values, &mut bingo_boards, &patterns);
+
+        let first_winner = first_winner.unwrap();
+        assert_eq!(first_winner.winning_pattern, Some(WinPattern::Diagonals));
+        assert_eq!(first_winner.winning_val, Some(9));
+        let last_winner = last_winner.unwrap();
+        assert_eq!(last_winner.winning_pattern, Some(WinPattern::FourCorners));
+        assert_eq!(last_winner.winning_val, Some(7));
+
+        // Neither board completes a row or column
+        let (_, mut bingo_boards) = load_input_data(input, None).unwrap();
+        let (first_winner, _) = run_game(&values, &mut bingo_boards, &WinPattern::DEFAULT);
+        assert_eq!(first_winner, None);
+    }
+
+    #[test]
+    fn test_run_game_matches_scan() {
+        let input = fs::read_to_string("input.txt").unwrap();
+        let pattern_sets = [
+            WinPattern::DEFAULT.to_vec(),
+            vec![WinPattern::Diagonals, WinPattern::FourCorners],
+            vec![WinPattern::X, WinPattern::Rows],
+        ];
+
+        for patterns in pattern_sets {
+            let (values, mut indexed_boards) = load_input_data(&input, None).unwrap();
+            let mut scanned_boards = indexed_boards.clone();
+
+            let indexed_winners = run_game(&values, &mut indexed_boards, &patterns);
+            let scanned_winners = run_game_scan(&values, &mut scanned_boards, &patterns);
+            assert_eq!(indexed_winners, scanned_winners);
+            assert_eq!(indexed_boards, scanned_boards);
+        }
+    }
+
+    #[test]
+    fn test_repeated_draw() {
+        let mut test_board = BingoBoard::new(&[vec![1, 2], vec![3, 4]]);
+        let mut placement = 0;
+
+        let marked = test_board.mark_spaces(1, [(0, 0)], &mut placement, &WinPattern::DEFAULT);
+        assert_eq!(marked, vec![(0, 0)]);
+        let marked = test_board.mark_spaces(1, [(0, 0)], &mut placement, &WinPattern::DEFAULT);
+        assert!(marked.is_empty());
+        assert_eq!(test_board.marked_count, 1);
+        assert_eq!(test_board.row_marks, vec![1, 0]);
+        assert_eq!(test_board.col_marks, vec![1, 0]);
+        assert!(!test_board.is_winner);
+
+        test_board.mark_if_present(2, &mut placement, &WinPattern::DEFAULT);
+        assert!(test_board.is_winner);
+        assert_eq!(test_board.winning_val, Some(2));
+        assert_eq!(test_board.score_with(&final_score), (3 + 4) * 2);
+
+        // Drawing a winning board's values again leaves it as it was
+        let winner = test_board.clone();
+        test_board.mark_if_present(2, &mut placement, &WinPattern::DEFAULT);
+        assert_eq!(test_board, winner);
+        assert_eq!(placement, 1);
+    }
+
+    #[test]
+    fn test_reset() {
+        let (values, bingo_boards) = load_input_data(TEST_INPUT_DATA, None).unwrap();
+        let patterns = [WinPattern::Rows, WinPattern::X];
+        let mut played = bingo_boards.clone();
+        run_game(&values, &mut played, &patterns);
+
+        let index = BingoIndex::new(&bingo_boards);
+        for board in &mut played {
+            board.reset();
+        }
+        assert_eq!(played, bingo_boards);
+        // Replaying on the reset boards gives the same results as fresh boards
+        play_with_index(&values, &mut played, &index, &patterns, None);
+        let mut fresh = bingo_boards.clone();
+        run_game(&values, &mut fresh, &patterns);
+        assert_eq!(played, fresh);
+    }
+
+    #[test]
+    fn test_replay_with_other_patterns() {
+        let (values, bingo_boards) = load_input_data(TEST_INPUT_DATA, None).unwrap();
+        let mut played = bingo_boards.clone();
+        run_game(&values, &mut played, &[WinPattern::Rows, WinPattern::X]);
+
+        // The same number of patterns, but not the same lines
+        let patterns = [WinPattern::Rows, WinPattern::FourCorners];
+        for board in &mut played {
+            board.reset();
+        }
+        run_game(&values, &mut played, &patterns);
+        let mut fresh = bingo_boards.clone();
+        run_game(&values, &mut fresh, &patterns);
+        assert_eq!(played, fresh);
+    }
+
+    #[test]
+    fn test_duplicate_values_on_board() {
+        let mut test_board = BingoBoard::new(&[vec![1, 2, 1], vec![3, 1, 4]]);
+        let mut placement = 0;
+
+        test_board.mark_if_present(1, &mut placement, &WinPattern::DEFAULT);
+        assert_eq!(test_board.marked_count, 3);
+        assert_eq!(test_board.row_marks, vec![2, 1]);
+        assert_eq!(test_board.col_marks, vec![1, 1, 1]);
+        assert!(!test_board.is_winner);
+
+        test_board.mark_if_present(1, &mut placement, &WinPattern::DEFAULT);
+        assert_eq!(test_board.marked_count, 3);
+
+        test_board.mark_if_present(2, &mut placement, &WinPattern::DEFAULT);
+        assert_eq!(test_board.winning_pattern, Some(WinPattern::Rows));
+        assert_eq!(test_board.score_with(&final_score), (3 + 4) * 2);
+    }
+
+    #[test]
+    fn test_duplicates_agree_across_games() {
+        // Board 0 holds 5 twice and 5 is drawn twice; the second draw marks nothing on either board
+        let input = "5,7,5,1,8,2,9,6\n\n5 1 7\n2 5 3\n\n6 8 9\n5 2 4\n";
+        let patterns = [WinPattern::Rows, WinPattern::Columns];
+        let (values, mut indexed_boards) = load_input_data(input, None).unwrap();
+        let mut scanned_boards = indexed_boards.clone();
+        let results = solve(&values, &indexed_boards, &patterns);
+
+        let indexed_winners = run_game(&values, &mut indexed_boards, &patterns);
+        let scanned_winners = run_game_scan(&values, &mut scanned_boards, &patterns);
+        assert_eq!(indexed_winners, scanned_winners);
+        assert_eq!(indexed_boards, scanned_boards);
+
+        assert_eq!(indexed_boards[0].marked_count, 4);
+        assert_eq!(indexed_boards[0].winning_val, Some(1));
+        assert_eq!(indexed_boards[1].winning_val, Some(2));
+        assert_eq!(indexed_boards[1].winning_pattern, Some(WinPattern::Columns));
+        for (result, board) in results.iter().zip(&indexed_boards) {
+            assert_eq!(result.placement, board.placement);
+            assert_eq!(result.score, Some(board.score_with(&final_score)));
+        }
+    }
+
+    #[test]
+    fn test_score_rules() {
+        let (values, mut bingo_boards) = load_input_data(TEST_INPUT_DATA, None).unwrap();
+        let (first_winner, _) = run_game(&values, &mut bingo_boards, &WinPattern::DEFAULT);
+        let first_winner = first_winner.unwrap();
+
+        let score = |rule: ScoreRule| first_winner.score_with(&rule.numeric_scorer());
+        assert_eq!(score(ScoreRule::Default), 4512);
+        assert_eq!(score(ScoreRule::UnmarkedSum), 188);
+        assert_eq!(score(ScoreRule::Unmarked), 13);
+        assert_eq!(score(ScoreRule::Marked), 12);
+
+        assert!(ScoreRule::Unmarked.token_scorer().is_ok());
+        assert!(ScoreRule::UnmarkedSum.token_scorer().is_err());
+    }
+
+    #[test]
+    fn test_parse_value_range() {
+        assert_eq!(parse_value_range("0-99"), Ok(0..=99));
+        assert_eq!(parse_value_range(" 5 - 5 "), Ok(5..=5));
+        assert!(parse_value_range("9-1").is_err());
+        assert!(parse_value_range("12").is_err());
+    }
+
+    #[test]
+    fn test_parse_board_size() {
+        assert_eq!(parse_board_size("7"), Ok((7, 7)));
+        assert_eq!(parse_board_size("4x3"), Ok((4, 3)));
+        assert!(parse_board_size("0x3").is_err());
+        assert!(parse_board_size("ax3").is_err());
     }
 }
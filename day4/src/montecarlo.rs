@@ -0,0 +1,262 @@
+use std::{fmt::Write, ops::Range, thread};
+
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+
+use crate::{index::BingoIndex, pattern::WinPattern, play_with_index, BingoBoard};
+
+/// z-score of a two-sided 95% confidence interval
+const Z_95: f64 = 1.96;
+
+/// How often one board won first and last across the simulated games
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct WinCounts {
+    first: usize,
+    last: usize,
+}
+
+/// An estimated probability with its 95% Wilson score interval
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Estimate {
+    pub probability: f64,
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Estimate {
+    fn new(successes: usize, trials: usize) -> Self {
+        if trials == 0 {
+            return Self {
+                probability: 0.0,
+                low: 0.0,
+                high: 1.0,
+            };
+        }
+
+        let n = trials as f64;
+        let p = successes as f64 / n;
+        let z2 = Z_95 * Z_95;
+        let denominator = 1.0 + z2 / n;
+        let centre = (p + z2 / (2.0 * n)) / denominator;
+        let margin = Z_95 * (p * (1.0 - p) / n + z2 / (4.0 * n * n)).sqrt() / denominator;
+
+        Self {
+            probability: p,
+            low: (centre - margin).max(0.0),
+            high: (centre + margin).min(1.0),
+        }
+    }
+}
+
+/// Estimated chances of one board winning first and last
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BoardOdds {
+    pub board: usize,
+    pub first: Estimate,
+    pub last: Estimate,
+}
+
+/// Result of [simulate]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct WinOdds {
+    pub trials: usize,
+    pub seed: u64,
+    pub boards: Vec<BoardOdds>,
+}
+
+/// Plays `trials` games, each drawing every value of `pool` in a random order, and counts how often each
+/// board wins first and last.
+///
+/// Every game has its own random stream derived from `seed` and the game's number, so the results depend
+/// only on the seed, never on how the games are split between the `jobs` threads. Boards that win on the
+/// same draw are placed in board order, as in [crate::run_game].
+pub fn simulate(
+    boards: &[BingoBoard],
+    patterns: &[WinPattern],
+    pool: &[u32],
+    trials: usize,
+    seed: u64,
+    jobs: usize,
+) -> WinOdds {
+    let jobs = jobs.clamp(1, trials.max(1));
+    let trials_per_job = trials / jobs;
+
+    let counts = thread::scope(|scope| {
+        let children: Vec<_> = (0..jobs)
+            .map(|id| {
+                let start = id * trials_per_job;
+                let end = if id < jobs - 1 {
+                    start + trials_per_job
+                } else {
+                    trials
+                };
+                scope.spawn(move || simulate_range(boards, patterns, pool, start..end, seed))
+            })
+            .collect();
+
+        let mut counts = vec![WinCounts::default(); boards.len()];
+        for child in children {
+            for (total, job_counts) in counts.iter_mut().zip(child.join().unwrap()) {
+                total.first += job_counts.first;
+                total.last += job_counts.last;
+            }
+        }
+        counts
+    });
+
+    WinOdds {
+        trials,
+        seed,
+        boards: counts
+            .iter()
+            .enumerate()
+            .map(|(board, counts)| BoardOdds {
+                board,
+                first: Estimate::new(counts.first, trials),
+                last: Estimate::new(counts.last, trials),
+            })
+            .collect(),
+    }
+}
+
+/// Plays the games numbered `trials`, reusing one copy of the boards and one index of their spaces for every
+/// game
+fn simulate_range(
+    boards: &[BingoBoard],
+    patterns: &[WinPattern],
+    pool: &[u32],
+    trials: Range<usize>,
+    seed: u64,
+) -> Vec<WinCounts> {
+    let mut counts = vec![WinCounts::default(); boards.len()];
+    let mut draws = pool.to_vec();
+    let index = BingoIndex::new(boards);
+    let mut game = boards.to_vec();
+
+    for trial in trials {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(trial as u64);
+        draws.copy_from_slice(pool);
+        draws.shuffle(&mut rng);
+
+        for board in &mut game {
+            board.reset();
+        }
+        play_with_index(&draws, &mut game, &index, patterns, None);
+
+        let placements = game.iter().enumerate().filter_map(|(board_idx, board)| {
+            board.placement.map(|placement| (placement, board_idx))
+        });
+        if let Some((_, first)) = placements.clone().min() {
+            counts[first].first += 1;
+        }
+        if let Some((_, last)) = placements.max() {
+            counts[last].last += 1;
+        }
+    }
+
+    counts
+}
+
+impl WinOdds {
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail
+        writeln!(out, "Games: {} (seed {})\n", self.trials, self.seed).unwrap();
+        writeln!(
+            out,
+            "{:>6} {:>8} {:>17} {:>8} {:>17}",
+            "board", "first", "first_95%", "last", "last_95%"
+        )
+        .unwrap();
+        for odds in &self.boards {
+            writeln!(
+                out,
+                "{:>6} {:>8.4} {:>17} {:>8.4} {:>17}",
+                odds.board,
+                odds.first.probability,
+                format!("[{:.4}, {:.4}]", odds.first.low, odds.first.high),
+                odds.last.probability,
+                format!("[{:.4}, {:.4}]", odds.last.low, odds.last.high)
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("board,first,first_low,first_high,last,last_low,last_high\n");
+        for odds in &self.boards {
+            writeln!(
+                out,
+                "{},{},{},{},{},{},{}",
+                odds.board,
+                odds.first.probability,
+                odds.first.low,
+                odds.first.high,
+                odds.last.probability,
+                odds.last.low,
+                odds.last.high
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load_input_data;
+
+    const TEST_INPUT: &str = "1\n\n1 2\n3 4\n\n5 6\n7 8\n\n1 2\n3 4\n";
+
+    #[test]
+    fn test_estimate() {
+        let estimate = Estimate::new(50, 100);
+        assert_eq!(estimate.probability, 0.5);
+        assert!((estimate.low - 0.4038).abs() < 1e-4);
+        assert!((estimate.high - 0.5962).abs() < 1e-4);
+
+        let never = Estimate::new(0, 100);
+        assert_eq!(never.low, 0.0);
+        assert!(never.high > 0.0 && never.high < 0.05);
+    }
+
+    #[test]
+    fn test_simulate_is_seeded() {
+        let (_, boards) = load_input_data(TEST_INPUT, None).unwrap();
+        let pool: Vec<u32> = (1..=8).collect();
+
+        let single = simulate(&boards, &WinPattern::DEFAULT, &pool, 500, 7, 1);
+        let parallel = simulate(&boards, &WinPattern::DEFAULT, &pool, 500, 7, 3);
+        assert_eq!(single, parallel);
+        assert_ne!(
+            single,
+            simulate(&boards, &WinPattern::DEFAULT, &pool, 500, 8, 1)
+        );
+    }
+
+    #[test]
+    fn test_simulate_odds() {
+        let (_, boards) = load_input_data(TEST_INPUT, None).unwrap();
+        let pool: Vec<u32> = (1..=8).collect();
+        let odds = simulate(&boards, &WinPattern::DEFAULT, &pool, 2000, 1, 2);
+
+        // Boards 0 and 2 are identical, so board 2 can only win first when board 0 does not
+        assert_eq!(odds.boards[2].first.probability, 0.0);
+        assert_eq!(odds.boards[0].last.probability, 0.0);
+        let first_total: f64 = odds.boards.iter().map(|b| b.first.probability).sum();
+        assert!((first_total - 1.0).abs() < 1e-9);
+        // The two distinct layouts are symmetric, so each wins first about half of the time
+        let first = odds.boards[0].first;
+        assert!(first.low < 0.5 && 0.5 < first.high);
+    }
+}
@@ -0,0 +1,354 @@
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+
+/// A set of cells that must all be marked for a board to win
+pub type WinLine = Vec<(usize, usize)>;
+
+/// A shape that wins a game of bingo.
+///
+/// Each pattern expands to one or more [WinLine]s for a given board size. A board wins with the pattern
+/// as soon as every cell of any one of those lines is marked.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WinPattern {
+    /// Any full row
+    Rows,
+    /// Any full column
+    Columns,
+    /// Either of the two diagonals; only square boards have diagonals
+    Diagonals,
+    /// The four corner spaces
+    FourCorners,
+    /// Both diagonals at once; only square boards have diagonals
+    X,
+    /// Every space on the board
+    FullCard,
+    /// A user-defined mask of required spaces, indexed as `mask[row][col]`.
+    ///
+    /// Masks only apply to boards with the same dimensions.
+    Custom { name: String, mask: Vec<Vec<bool>> },
+}
+
+impl WinPattern {
+    /// The patterns used by a standard game
+    pub const DEFAULT: [WinPattern; 2] = [WinPattern::Rows, WinPattern::Columns];
+
+    /// Expands the pattern into the lines it covers on a `width` x `height` board
+    pub fn lines(&self, width: usize, height: usize) -> Vec<WinLine> {
+        let diagonal: WinLine = (0..width).map(|i| (i, i)).collect();
+        let anti_diagonal: WinLine = (0..width).map(|i| (i, width - 1 - i)).collect();
+        let is_square = width == height && width > 0;
+
+        match self {
+            WinPattern::Rows => (0..height)
+                .map(|y| (0..width).map(|x| (y, x)).collect())
+                .collect(),
+            WinPattern::Columns => (0..width)
+                .map(|x| (0..height).map(|y| (y, x)).collect())
+                .collect(),
+            WinPattern::Diagonals if is_square => vec![diagonal, anti_diagonal],
+            WinPattern::X if is_square => {
+                let mut cells = diagonal;
+                cells.extend(anti_diagonal.into_iter().filter(|&(y, x)| y != x));
+                vec![cells]
+            }
+            WinPattern::Diagonals | WinPattern::X => vec![],
+            WinPattern::FourCorners if width > 0 && height > 0 => {
+                let mut corners = vec![
+                    (0, 0),
+                    (0, width - 1),
+                    (height - 1, 0),
+                    (height - 1, width - 1),
+                ];
+                corners.sort();
+                corners.dedup();
+                vec![corners]
+            }
+            WinPattern::FourCorners => vec![],
+            WinPattern::FullCard => vec![(0..height)
+                .flat_map(|y| (0..width).map(move |x| (y, x)))
+                .collect()],
+            WinPattern::Custom { mask, .. } => {
+                let fits = mask.len() == height && mask.iter().all(|row| row.len() == width);
+                if !fits {
+                    return vec![];
+                }
+                let cells: WinLine = mask
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(y, row)| {
+                        row.iter()
+                            .enumerate()
+                            .filter(|(_, &required)| required)
+                            .map(move |(x, _)| (y, x))
+                    })
+                    .collect();
+                if cells.is_empty() {
+                    vec![]
+                } else {
+                    vec![cells]
+                }
+            }
+        }
+    }
+}
+
+/// The lines a set of patterns expands to on one board, with how many spaces of each line are marked, so
+/// marking a space only has to update the lines through it.
+///
+/// Rows and columns are left out, since boards count the marks in those themselves.
+#[derive(Debug, Clone, Default)]
+pub struct PatternLines {
+    width: usize,
+    /// The patterns the lines were expanded from
+    patterns: Vec<WinPattern>,
+    /// Index of each line's pattern in [PatternLines::patterns]
+    line_patterns: Vec<usize>,
+    /// Number of spaces in each line
+    line_lengths: Vec<u32>,
+    /// Number of marked spaces in each line
+    line_marks: Vec<u32>,
+    /// Indices of the lines through each space, indexed by `row * width + col`
+    lines_at: Vec<Vec<usize>>,
+}
+
+impl PatternLines {
+    /// Expands `patterns` on a `width` x `height` board, counting the spaces already marked according to
+    /// `is_marked(row, col)`
+    pub fn new(
+        patterns: &[WinPattern],
+        width: usize,
+        height: usize,
+        is_marked: impl Fn(usize, usize) -> bool,
+    ) -> Self {
+        let mut pattern_lines = Self {
+            width,
+            patterns: patterns.to_vec(),
+            lines_at: vec![vec![]; width * height],
+            ..Self::default()
+        };
+        for (pattern_idx, pattern) in patterns.iter().enumerate() {
+            if matches!(pattern, WinPattern::Rows | WinPattern::Columns) {
+                continue;
+            }
+            for line in pattern.lines(width, height) {
+                let line_idx = pattern_lines.line_patterns.len();
+                pattern_lines.line_patterns.push(pattern_idx);
+                pattern_lines.line_lengths.push(line.len() as u32);
+                pattern_lines
+                    .line_marks
+                    .push(line.iter().filter(|&&(y, x)| is_marked(y, x)).count() as u32);
+                for (y, x) in line {
+                    pattern_lines.lines_at[y * width + x].push(line_idx);
+                }
+            }
+        }
+
+        pattern_lines
+    }
+
+    /// The patterns the lines were expanded from
+    pub fn patterns(&self) -> &[WinPattern] {
+        &self.patterns
+    }
+
+    /// Counts a newly marked space towards every line through it
+    pub fn mark(&mut self, row: usize, col: usize) {
+        for &line_idx in &self.lines_at[row * self.width + col] {
+            self.line_marks[line_idx] += 1;
+        }
+    }
+
+    /// Sets every line back to having no marked spaces
+    pub fn clear_marks(&mut self) {
+        self.line_marks.fill(0);
+    }
+
+    /// Whether a line of the pattern at `pattern_idx` through `(row, col)` is fully marked
+    pub fn completed_at(&self, pattern_idx: usize, row: usize, col: usize) -> bool {
+        self.lines_at[row * self.width + col]
+            .iter()
+            .any(|&line_idx| {
+                self.line_patterns[line_idx] == pattern_idx
+                    && self.line_marks[line_idx] == self.line_lengths[line_idx]
+            })
+    }
+}
+
+impl fmt::Display for WinPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WinPattern::Rows => write!(f, "row"),
+            WinPattern::Columns => write!(f, "column"),
+            WinPattern::Diagonals => write!(f, "diagonal"),
+            WinPattern::FourCorners => write!(f, "four corners"),
+            WinPattern::X => write!(f, "x"),
+            WinPattern::FullCard => write!(f, "full card"),
+            WinPattern::Custom { name, .. } => write!(f, "{name}"),
+        }
+    }
+}
+
+impl FromStr for WinPattern {
+    type Err = String;
+
+    /// Parses the name of a built-in pattern
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "rows" | "row" => Ok(WinPattern::Rows),
+            "columns" | "column" | "cols" => Ok(WinPattern::Columns),
+            "diagonals" | "diagonal" => Ok(WinPattern::Diagonals),
+            "corners" | "four-corners" => Ok(WinPattern::FourCorners),
+            "x" => Ok(WinPattern::X),
+            "full" | "full-card" | "blackout" => Ok(WinPattern::FullCard),
+            _ => Err(format!(
+                "unknown pattern '{s}'; expected one of rows, columns, diagonals, corners, x, full-card"
+            )),
+        }
+    }
+}
+
+/// Loads custom patterns from a mask file.
+///
+/// Masks are separated by blank lines. Each mask may start with a `name: <name>` line, followed by one
+/// line per board row where `X` or `#` marks a required space and `.` marks any other space.
+/// Whitespace within rows is ignored.
+pub fn load_custom_patterns(input: &str) -> anyhow::Result<Vec<WinPattern>> {
+    let mut patterns = vec![];
+    let mut name: Option<String> = None;
+    let mut mask: Vec<Vec<bool>> = vec![];
+
+    let mut finish_pattern = |name: &mut Option<String>, mask: &mut Vec<Vec<bool>>| {
+        if !mask.is_empty() {
+            let name = name
+                .take()
+                .unwrap_or_else(|| format!("custom {}", patterns.len() + 1));
+            patterns.push(WinPattern::Custom {
+                name,
+                mask: std::mem::take(mask),
+            });
+        }
+    };
+
+    for (line_idx, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            finish_pattern(&mut name, &mut mask);
+            continue;
+        }
+        if let Some(pattern_name) = line.strip_prefix("name:") {
+            finish_pattern(&mut name, &mut mask);
+            name = Some(pattern_name.trim().to_string());
+            continue;
+        }
+
+        let row = line
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c {
+                'X' | 'x' | '#' => Ok(true),
+                '.' => Ok(false),
+                _ => Err(anyhow::anyhow!(
+                    "Invalid character '{c}' in pattern mask on line {}",
+                    line_idx + 1
+                )),
+            })
+            .collect::<anyhow::Result<Vec<bool>>>()?;
+        mask.push(row);
+    }
+    finish_pattern(&mut name, &mut mask);
+
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rows_and_columns() {
+        assert_eq!(
+            WinPattern::Rows.lines(3, 2),
+            vec![vec![(0, 0), (0, 1), (0, 2)], vec![(1, 0), (1, 1), (1, 2)]]
+        );
+        assert_eq!(
+            WinPattern::Columns.lines(2, 2),
+            vec![vec![(0, 0), (1, 0)], vec![(0, 1), (1, 1)]]
+        );
+    }
+
+    #[test]
+    fn test_diagonals() {
+        assert_eq!(
+            WinPattern::Diagonals.lines(3, 3),
+            vec![vec![(0, 0), (1, 1), (2, 2)], vec![(0, 2), (1, 1), (2, 0)]]
+        );
+        assert_eq!(
+            WinPattern::X.lines(3, 3),
+            vec![vec![(0, 0), (1, 1), (2, 2), (0, 2), (2, 0)]]
+        );
+        assert!(WinPattern::Diagonals.lines(3, 4).is_empty());
+    }
+
+    #[test]
+    fn test_four_corners_and_full_card() {
+        assert_eq!(
+            WinPattern::FourCorners.lines(5, 3),
+            vec![vec![(0, 0), (0, 4), (2, 0), (2, 4)]]
+        );
+        assert_eq!(WinPattern::FullCard.lines(4, 3)[0].len(), 12);
+    }
+
+    #[test]
+    fn test_pattern_lines() {
+        let patterns = [
+            WinPattern::Rows,
+            WinPattern::Diagonals,
+            WinPattern::FourCorners,
+        ];
+        // The centre is marked before the lines are built
+        let mut lines = PatternLines::new(&patterns, 3, 3, |y, x| (y, x) == (1, 1));
+        assert_eq!(lines.patterns(), patterns);
+
+        lines.mark(0, 0);
+        assert!(!lines.completed_at(1, 0, 0));
+        lines.mark(2, 2);
+        assert!(lines.completed_at(1, 2, 2));
+        assert!(lines.completed_at(1, 1, 1));
+        // The anti-diagonal and the corners are still incomplete, and rows are not tracked
+        assert!(!lines.completed_at(2, 2, 2));
+        assert!(!lines.completed_at(0, 0, 0));
+
+        lines.mark(0, 2);
+        lines.mark(2, 0);
+        assert!(lines.completed_at(2, 0, 2));
+        assert!(lines.completed_at(1, 2, 0));
+    }
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("Rows".parse(), Ok(WinPattern::Rows));
+        assert_eq!("blackout".parse(), Ok(WinPattern::FullCard));
+        assert!("plus".parse::<WinPattern>().is_err());
+    }
+
+    #[test]
+    fn test_load_custom_patterns() {
+        let input = "name: plus\n.X.\nXXX\n.X.\n\n# . #\n. . .\n# . #\n";
+        let patterns = load_custom_patterns(input).unwrap();
+
+        assert_eq!(patterns.len(), 2);
+        assert_eq!(patterns[0].to_string(), "plus");
+        assert_eq!(
+            patterns[0].lines(3, 3),
+            vec![vec![(0, 1), (1, 0), (1, 1), (1, 2), (2, 1)]]
+        );
+        assert_eq!(patterns[1].to_string(), "custom 2");
+        assert_eq!(patterns[1].lines(3, 3), WinPattern::FourCorners.lines(3, 3));
+        // Masks only apply to boards of the same size
+        assert!(patterns[0].lines(5, 5).is_empty());
+
+        assert!(load_custom_patterns("X?X\n").is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use std::fmt::Write;
+
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{cell::Scorer, BingoBoard};
+
+/// One row of the rank table
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardRank {
+    pub board: usize,
+    /// 0-based order in which the board won
+    pub placement: Option<usize>,
+    /// Index into the drawn values of the draw that completed the board
+    pub turn: Option<usize>,
+    pub winning_val: Option<u32>,
+    pub unmarked_sum: u32,
+    pub score: Option<u32>,
+}
+
+/// Column to sort the rank table by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RankSort {
+    Placement,
+    Board,
+    Turn,
+    Unmarked,
+    Score,
+}
+
+/// Every board's result after a game, ordered by placement with boards that never won at the end
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankTable {
+    pub ranks: Vec<BoardRank>,
+}
+
+impl RankTable {
+    /// Builds the table from the boards' state after the `vals` have been played, scoring the winners with
+    /// `scorer`.
+    ///
+    /// Since repeated draws never mark anything, a board won on the first draw of its winning value.
+    pub fn new(vals: &[u32], boards: &[BingoBoard], scorer: &impl Scorer<u32>) -> Self {
+        let ranks = boards
+            .iter()
+            .enumerate()
+            .map(|(board_idx, board)| {
+                let won = board.is_winner && board.placement.is_some();
+                let turn = board
+                    .winning_val
+                    .filter(|_| won)
+                    .and_then(|winning_val| vals.iter().position(|&val| val == winning_val));
+                BoardRank {
+                    board: board_idx,
+                    placement: board.placement.filter(|_| won),
+                    turn,
+                    winning_val: board.winning_val.filter(|_| won),
+                    unmarked_sum: board.sum_of_unmarked(),
+                    score: won.then(|| board.score_with(scorer)),
+                }
+            })
+            .collect();
+
+        let mut table = Self { ranks };
+        table.sort(RankSort::Placement, false);
+        table
+    }
+
+    /// Sorts the table by `key`, ascending unless `descending`.
+    ///
+    /// Boards that never won stay at the end in board order, whichever way the table is sorted.
+    pub fn sort(&mut self, key: RankSort, descending: bool) {
+        self.ranks.sort_by(|a, b| {
+            let by_key = match key {
+                RankSort::Placement => a.placement.cmp(&b.placement),
+                RankSort::Board => a.board.cmp(&b.board),
+                RankSort::Turn => a.turn.cmp(&b.turn),
+                RankSort::Unmarked => a.unmarked_sum.cmp(&b.unmarked_sum),
+                RankSort::Score => a.score.cmp(&b.score),
+            };
+            let by_key = if descending { by_key.reverse() } else { by_key };
+
+            a.placement
+                .is_none()
+                .cmp(&b.placement.is_none())
+                .then(by_key)
+                .then(a.board.cmp(&b.board))
+        });
+    }
+
+    /// Formats the table for reading; ranks count from 1, unlike the 0-based placements in the exports
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` cannot fail
+        writeln!(
+            out,
+            "{:>6} {:>5} {:>5} {:>11} {:>12} {:>8}",
+            "board", "rank", "turn", "winning_val", "unmarked_sum", "score"
+        )
+        .unwrap();
+        for rank in &self.ranks {
+            writeln!(
+                out,
+                "{:>6} {:>5} {:>5} {:>11} {:>12} {:>8}",
+                rank.board,
+                optional(rank.placement.map(|placement| placement + 1), "-"),
+                optional(rank.turn, "-"),
+                optional(rank.winning_val, "-"),
+                rank.unmarked_sum,
+                optional(rank.score, "-")
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("board,placement,turn,winning_val,unmarked_sum,score\n");
+        for rank in &self.ranks {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                rank.board,
+                optional(rank.placement, ""),
+                optional(rank.turn, ""),
+                optional(rank.winning_val, ""),
+                rank.unmarked_sum,
+                optional(rank.score, "")
+            )
+            .unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.ranks)
+    }
+}
+
+/// Formats an optional column; using `missing` for boards that never won
+fn optional<T: ToString>(value: Option<T>, missing: &str) -> String {
+    value.map_or_else(|| missing.to_string(), |value| value.to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cell::final_score, cell::marked_count, load_input_data, pattern::WinPattern, run_game,
+    };
+
+    const INPUT: &str = "5,1,9,2,6\n\n1 2 3 4\n5 6 7 8\n\n9 1 2 0\n5 6 3 4\n\n7 7 7 7\n8 8 8 8\n";
+
+    fn ranked_with(scorer: &impl Scorer<u32>) -> RankTable {
+        let (values, mut boards) = load_input_data(INPUT, None).unwrap();
+        run_game(&values, &mut boards, &WinPattern::DEFAULT);
+        RankTable::new(&values, &boards, scorer)
+    }
+
+    fn ranked() -> RankTable {
+        ranked_with(&final_score)
+    }
+
+    #[test]
+    fn test_rank_table() {
+        let table = ranked();
+
+        assert_eq!(
+            table.ranks[0],
+            BoardRank {
+                board: 0,
+                placement: Some(0),
+                turn: Some(1),
+                winning_val: Some(1),
+                unmarked_sum: 2 + 3 + 4 + 6 + 7 + 8,
+                score: Some(30)
+            }
+        );
+        assert_eq!(table.ranks[1].board, 1);
+        assert_eq!(table.ranks[1].turn, Some(2));
+        assert_eq!(table.ranks[2].board, 2);
+        assert_eq!(table.ranks[2].placement, None);
+        assert_eq!(table.ranks[2].score, None);
+        assert_eq!(table.ranks[2].unmarked_sum, 60);
+    }
+
+    #[test]
+    fn test_rank_with_scorer() {
+        let table = ranked_with(&marked_count);
+        let scores: Vec<Option<u32>> = table.ranks.iter().map(|rank| rank.score).collect();
+        assert_eq!(scores, vec![Some(2), Some(3), None]);
+
+        // Any closure is a scorer; here a board scores the value it won on
+        let mut table = ranked_with(&|board: &BingoBoard| board.winning_val.unwrap_or(0));
+        table.sort(RankSort::Score, true);
+        let scores: Vec<Option<u32>> = table.ranks.iter().map(|rank| rank.score).collect();
+        assert_eq!(scores, vec![Some(9), Some(1), None]);
+    }
+
+    #[test]
+    fn test_sort_keeps_non_winners_last() {
+        let mut table = ranked();
+        table.sort(RankSort::Score, true);
+        let boards: Vec<usize> = table.ranks.iter().map(|rank| rank.board).collect();
+        assert_eq!(boards, vec![1, 0, 2]);
+
+        table.sort(RankSort::Board, true);
+        let boards: Vec<usize> = table.ranks.iter().map(|rank| rank.board).collect();
+        assert_eq!(boards, vec![1, 0, 2]);
+    }
+
+    #[test]
+    fn test_rank_export() {
+        let table = ranked();
+
+        let csv = table.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "board,placement,turn,winning_val,unmarked_sum,score"
+        );
+        assert_eq!(lines[1], "0,0,1,1,30,30");
+        assert_eq!(lines[3], "2,,,,60,");
+
+        let json: serde_json::Value = serde_json::from_str(&table.to_json().unwrap()).unwrap();
+        assert_eq!(json[2]["board"], 2);
+        assert_eq!(json[2]["score"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,237 @@
+use clap::ValueEnum;
+
+use crate::BingoBoard;
+
+const ANSI_RESET: &str = "\x1b[0m";
+const ANSI_BOLD: &str = "\x1b[1m";
+const ANSI_WIN: &str = "\x1b[1;30;42m";
+
+/// How marked spaces are highlighted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderStyle {
+    /// Marked spaces in `[brackets]` and the winning line in `<angle brackets>`
+    Plain,
+    /// Marked spaces in bold and the winning line in bold on green
+    Ansi,
+}
+
+/// When to use [RenderStyle::Ansi]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Only when stdout is a terminal
+    Auto,
+    Always,
+    Never,
+}
+
+/// Draws [BingoBoard]s as text grids
+#[derive(Debug, Clone)]
+pub struct BoardRenderer {
+    style: RenderStyle,
+    /// Columns used by the widest value
+    value_width: usize,
+}
+
+/// Columns between boards laid out side by side
+const BOARD_GAP: usize = 3;
+
+impl BoardRenderer {
+    /// Creates a renderer whose cells fit every value on the given boards
+    pub fn new<'a>(style: RenderStyle, boards: impl IntoIterator<Item = &'a BingoBoard>) -> Self {
+        let max_value = boards
+            .into_iter()
+            .flat_map(|board| board.spaces.iter().flatten())
+            .map(|space| space.value)
+            .max()
+            .unwrap_or(0);
+
+        Self {
+            style,
+            value_width: max_value.to_string().len().max(2),
+        }
+    }
+
+    /// Columns taken by one space, including its highlight characters
+    fn cell_width(&self) -> usize {
+        self.value_width + 2
+    }
+
+    /// Columns taken by a board's rows
+    pub fn board_width(&self, board: &BingoBoard) -> usize {
+        board.width * self.cell_width()
+    }
+
+    /// Renders each row of the board, with its winning line emphasised if it has won
+    pub fn render_board(&self, board: &BingoBoard) -> Vec<String> {
+        let winning_line = board.winning_line().unwrap_or_default();
+        let width = self.value_width;
+
+        board
+            .spaces
+            .iter()
+            .enumerate()
+            .map(|(y, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(x, space)| {
+                        let value = space.value;
+                        let winning = winning_line.contains(&(y, x));
+                        match (self.style, space.marked, winning) {
+                            (RenderStyle::Plain, _, true) => format!("<{value:>width$}>"),
+                            (RenderStyle::Plain, true, false) => format!("[{value:>width$}]"),
+                            (RenderStyle::Ansi, _, true) => {
+                                format!(" {ANSI_WIN}{value:>width$}{ANSI_RESET} ")
+                            }
+                            (RenderStyle::Ansi, true, false) => {
+                                format!(" {ANSI_BOLD}{value:>width$}{ANSI_RESET} ")
+                            }
+                            (_, false, _) => format!(" {value:>width$} "),
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Lays out titled boards side by side; starting a new band of boards whenever the next one would
+    /// not fit in `max_width` columns
+    pub fn render_boards(&self, boards: &[(String, &BingoBoard)], max_width: usize) -> String {
+        let mut bands: Vec<Vec<&(String, &BingoBoard)>> = vec![];
+        let mut band_width = 0;
+        for entry in boards {
+            let width = self.board_width(entry.1);
+            match bands.last_mut() {
+                Some(band) if band_width + BOARD_GAP + width <= max_width => {
+                    band.push(entry);
+                    band_width += BOARD_GAP + width;
+                }
+                _ => {
+                    bands.push(vec![entry]);
+                    band_width = width;
+                }
+            }
+        }
+
+        let mut output = String::new();
+        for (band_idx, band) in bands.iter().enumerate() {
+            if band_idx > 0 {
+                output.push('\n');
+            }
+            let height = band
+                .iter()
+                .map(|(_, board)| board.height)
+                .max()
+                .unwrap_or(0);
+            let columns: Vec<Vec<String>> = band
+                .iter()
+                .map(|(title, board)| {
+                    let width = self.board_width(board);
+                    let mut lines = vec![format!("{:<width$.width$}", title)];
+                    lines.extend(self.render_board(board));
+                    lines.resize(height + 1, " ".repeat(width));
+                    lines
+                })
+                .collect();
+
+            for line_idx in 0..=height {
+                let line: Vec<&str> = columns
+                    .iter()
+                    .map(|lines| lines[line_idx].as_str())
+                    .collect();
+                output.push_str(line.join(&" ".repeat(BOARD_GAP)).trim_end());
+                output.push('\n');
+            }
+        }
+
+        output
+    }
+}
+
+/// Width of the terminal standard output is written to; falling back to the `COLUMNS` environment variable
+/// when standard output is not a terminal, or 80 columns if that is not set either
+pub fn terminal_width() -> usize {
+    stdout_columns()
+        .or_else(|| {
+            std::env::var("COLUMNS")
+                .ok()
+                .and_then(|columns| columns.parse().ok())
+        })
+        .filter(|&columns| columns > 0)
+        .unwrap_or(80)
+}
+
+/// Width of the terminal standard output is written to, if it is a terminal
+#[cfg(unix)]
+fn stdout_columns() -> Option<usize> {
+    let mut size = libc::winsize {
+        ws_row: 0,
+        ws_col: 0,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: `TIOCGWINSZ` only writes a `winsize` through the pointer it is given
+    let result = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+    (result == 0 && size.ws_col > 0).then_some(size.ws_col as usize)
+}
+
+#[cfg(not(unix))]
+fn stdout_columns() -> Option<usize> {
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pattern::WinPattern;
+
+    fn won_board() -> BingoBoard {
+        let mut board = BingoBoard::new(&[vec![1, 2, 3], vec![4, 5, 60]]);
+        let mut placement = 0;
+        // Row 0 wins on 2; marking 4 afterwards completes column 0, which is not the winning line
+        for value in [1, 3, 2, 4] {
+            board.mark_if_present(value, &mut placement, &WinPattern::DEFAULT);
+        }
+        board
+    }
+
+    #[test]
+    fn test_render_plain() {
+        let board = won_board();
+        let renderer = BoardRenderer::new(RenderStyle::Plain, [&board]);
+
+        assert_eq!(
+            renderer.render_board(&board),
+            vec!["< 1>< 2>< 3>", "[ 4]  5  60 "]
+        );
+    }
+
+    #[test]
+    fn test_render_ansi() {
+        let board = won_board();
+        let renderer = BoardRenderer::new(RenderStyle::Ansi, [&board]);
+        let rows = renderer.render_board(&board);
+
+        assert!(rows[0].starts_with(&format!(" {ANSI_WIN} 1{ANSI_RESET} ")));
+        assert_eq!(rows[1], format!(" {ANSI_BOLD} 4{ANSI_RESET}   5  60 "));
+    }
+
+    #[test]
+    fn test_render_boards_wraps() {
+        let board = won_board();
+        let renderer = BoardRenderer::new(RenderStyle::Plain, [&board]);
+        let boards = vec![
+            ("a".to_string(), &board),
+            ("b".to_string(), &board),
+            ("c".to_string(), &board),
+        ];
+
+        // Each board is 12 columns wide, so two fit side by side in 30 columns
+        let output = renderer.render_boards(&boards, 30);
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3 + 1 + 3);
+        assert_eq!(lines[0], format!("{:<15}b", "a"));
+        assert_eq!(lines[1], "< 1>< 2>< 3>   < 1>< 2>< 3>");
+        assert_eq!(lines[3], "");
+        assert_eq!(lines[4], "c");
+    }
+}
@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use crate::{pattern::WinPattern, BingoBoard};
+
+/// The outcome of a game for one board, as computed by [solve]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoardResult {
+    pub board_idx: usize,
+    /// Index into the drawn values of the draw that completed the board
+    pub win_turn: Option<usize>,
+    pub winning_val: Option<u32>,
+    pub winning_pattern: Option<WinPattern>,
+    pub placement: Option<usize>,
+    pub score: Option<u32>,
+}
+
+/// Computes every board's result directly from the draw order, without replaying the game.
+///
+/// Each value is mapped to the turn it is first drawn on. A line completes on the latest turn of any of
+/// its spaces, and a board wins on the earliest turn that completes any of its lines. Ties between
+/// patterns go to the first one in `patterns`, and boards winning on the same turn are placed in board
+/// order, both matching [crate::run_game].
+pub fn solve(vals: &[u32], boards: &[BingoBoard], patterns: &[WinPattern]) -> Vec<BoardResult> {
+    let mut turns: HashMap<u32, usize> = HashMap::with_capacity(vals.len());
+    for (turn, &val) in vals.iter().enumerate() {
+        turns.entry(val).or_insert(turn);
+    }
+
+    let mut results: Vec<BoardResult> = boards
+        .iter()
+        .enumerate()
+        .map(|(board_idx, board)| solve_board(board_idx, board, vals, &turns, patterns))
+        .collect();
+
+    let mut winners: Vec<&mut BoardResult> = results
+        .iter_mut()
+        .filter(|result| result.win_turn.is_some())
+        .collect();
+    winners.sort_by_key(|result| (result.win_turn, result.board_idx));
+    for (placement, result) in winners.into_iter().enumerate() {
+        result.placement = Some(placement);
+    }
+
+    results
+}
+
+fn solve_board(
+    board_idx: usize,
+    board: &BingoBoard,
+    vals: &[u32],
+    turns: &HashMap<u32, usize>,
+    patterns: &[WinPattern],
+) -> BoardResult {
+    let turn_of = |(y, x): (usize, usize)| turns.get(&board.spaces[y][x].value).copied();
+
+    // (turn, pattern index) of the earliest completed line
+    let mut best: Option<(usize, usize)> = None;
+    for (pattern_idx, pattern) in patterns.iter().enumerate() {
+        for line in pattern.lines(board.width, board.height) {
+            let line_turn = line
+                .iter()
+                .map(|&space| turn_of(space))
+                .try_fold(0, |latest, turn| turn.map(|turn| latest.max(turn)));
+            if let Some(line_turn) = line_turn {
+                if best.is_none_or(|best| (line_turn, pattern_idx) < best) {
+                    best = Some((line_turn, pattern_idx));
+                }
+            }
+        }
+    }
+
+    let Some((win_turn, pattern_idx)) = best else {
+        return BoardResult {
+            board_idx,
+            win_turn: None,
+            winning_val: None,
+            winning_pattern: None,
+            placement: None,
+            score: None,
+        };
+    };
+
+    let winning_val = vals[win_turn];
+    let sum_of_unmarked: u32 = board
+        .spaces
+        .iter()
+        .flatten()
+        .filter(|space| turns.get(&space.value).is_none_or(|&turn| turn > win_turn))
+        .map(|space| space.value)
+        .sum();
+
+    BoardResult {
+        board_idx,
+        win_turn: Some(win_turn),
+        winning_val: Some(winning_val),
+        winning_pattern: Some(patterns[pattern_idx].clone()),
+        placement: None,
+        score: Some(sum_of_unmarked * winning_val),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::*;
+    use crate::{cell::final_score, load_input_data, run_game};
+
+    /// Checks every board's result against a simulated game
+    fn assert_matches_simulation(input: &str, patterns: &[WinPattern]) {
+        let (values, mut boards) = load_input_data(input, None).unwrap();
+        let results = solve(&values, &boards, patterns);
+        run_game(&values, &mut boards, patterns);
+
+        assert_eq!(results.len(), boards.len());
+        for (result, board) in results.iter().zip(&boards) {
+            assert_eq!(
+                result.placement, board.placement,
+                "board {}",
+                result.board_idx
+            );
+            assert_eq!(result.winning_val, board.winning_val);
+            assert_eq!(result.winning_pattern, board.winning_pattern);
+            if board.is_winner {
+                assert_eq!(result.score, Some(board.score_with(&final_score)));
+            } else {
+                assert_eq!(result.score, None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_solve_sample() {
+        let input = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1\n\n\
+                     22 13 17 11  0\n 8  2 23  4 24\n21  9 14 16  7\n 6 10  3 18  5\n 1 12 20 15 19\n\n\
+                      3 15  0  2 22\n 9 18 13 17  5\n19  8  7 25 23\n20 11 10 24  4\n14 21 16 12  6\n\n\
+                     14 21 17 24  4\n10 16 15  9 19\n18  8 23 26 20\n22 11 13  6  5\n 2  0 12  3  7";
+        let (values, boards) = load_input_data(input, None).unwrap();
+        let results = solve(&values, &boards, &WinPattern::DEFAULT);
+
+        assert_eq!(results[2].placement, Some(0));
+        assert_eq!(results[2].win_turn, Some(11));
+        assert_eq!(results[2].score, Some(4512));
+        assert_eq!(results[1].placement, Some(2));
+        assert_eq!(results[1].score, Some(1924));
+    }
+
+    #[test]
+    fn test_solve_matches_simulation() {
+        let input = fs::read_to_string("input.txt").unwrap();
+        let pattern_sets = [
+            WinPattern::DEFAULT.to_vec(),
+            vec![WinPattern::Diagonals, WinPattern::FourCorners],
+            vec![WinPattern::FullCard],
+        ];
+
+        for patterns in pattern_sets {
+            assert_matches_simulation(&input, &patterns);
+        }
+    }
+
+    #[test]
+    fn test_solve_never_wins() {
+        let input = "1,2\n\n1 2\n3 4\n\n5 1\n6 2\n";
+        let (values, boards) = load_input_data(input, None).unwrap();
+        let results = solve(&values, &boards, &[WinPattern::Columns]);
+
+        assert_eq!(results[0].win_turn, None);
+        assert_eq!(results[0].placement, None);
+        assert_eq!(results[1].win_turn, Some(1));
+        assert_eq!(results[1].placement, Some(0));
+        assert_eq!(results[1].score, Some(11 * 2));
+    }
+}
@@ -0,0 +1,458 @@
+use std::{collections::HashMap, error::Error, fmt, fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::{pattern::WinPattern, BingoBoard, BingoSpace};
+
+/// Version of the saved state's layout; bumped whenever a saved game could no longer be read
+const STATE_VERSION: u32 = 2;
+
+/// A problem with a loaded game state that would stop the game from being resumed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateError {
+    /// The state was saved with a different layout
+    Version { found: u32 },
+    /// More values have been drawn than the game has
+    Cursor { cursor: usize, draws: usize },
+    /// A board's rows are not all as wide as its first
+    BoardShape {
+        board: usize,
+        width: usize,
+        height: usize,
+    },
+    /// A board's marked spaces are not the ones holding the values drawn before it won
+    BoardMarks { board: usize },
+    /// A board won on a value that has not been drawn, or with a pattern the game is not played with
+    BoardResult { board: usize },
+    /// The winning boards do not each have one of the placements given out so far
+    Placements { placement: usize },
+}
+
+impl fmt::Display for StateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StateError::Version { found } => write!(
+                f,
+                "version {found} game state; expected version {STATE_VERSION}"
+            ),
+            StateError::Cursor { cursor, draws } => write!(
+                f,
+                "{cursor} values have been drawn but only {draws} are available"
+            ),
+            StateError::BoardShape {
+                board,
+                width,
+                height,
+            } => write!(
+                f,
+                "board {board} does not have {height} rows of {width} spaces"
+            ),
+            StateError::BoardMarks { board } => {
+                write!(
+                    f,
+                    "board {board}'s marked spaces do not match the values drawn"
+                )
+            }
+            StateError::BoardResult { board } => {
+                write!(
+                    f,
+                    "board {board} won on a value or pattern that is not in the game"
+                )
+            }
+            StateError::Placements { placement } => write!(
+                f,
+                "the winning boards do not each have one of the first {placement} placements"
+            ),
+        }
+    }
+}
+
+impl Error for StateError {}
+
+/// A game in progress, saved between draws
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameState {
+    /// Every value to be drawn, in order
+    pub draws: Vec<u32>,
+    /// Index of the next value to draw
+    pub cursor: usize,
+    /// Placement the next board to win will get
+    pub placement: usize,
+    pub patterns: Vec<WinPattern>,
+    pub boards: Vec<BingoBoard>,
+}
+
+impl GameState {
+    /// Sets up a game where nothing has been drawn yet
+    pub fn new(draws: Vec<u32>, boards: Vec<BingoBoard>, patterns: Vec<WinPattern>) -> Self {
+        Self {
+            draws,
+            cursor: 0,
+            placement: 0,
+            patterns,
+            boards,
+        }
+    }
+
+    /// The values still to be drawn
+    pub fn remaining(&self) -> &[u32] {
+        &self.draws[self.cursor..]
+    }
+
+    /// Draws up to `count` values, stopping early when the draws run out.
+    ///
+    /// Returns the indices of the boards that won, in the order they won.
+    pub fn draw(&mut self, count: usize) -> Vec<usize> {
+        let mut winners = vec![];
+        let end = (self.cursor + count).min(self.draws.len());
+        for &val in &self.draws[self.cursor..end] {
+            for (board_idx, board) in self.boards.iter_mut().enumerate() {
+                if !board.is_winner {
+                    board.mark_if_present(val, &mut self.placement, &self.patterns);
+                    if board.is_winner {
+                        winners.push(board_idx);
+                    }
+                }
+            }
+        }
+        self.cursor = end;
+
+        winners
+    }
+
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let saved: SavedGame = serde_json::from_str(&fs::read_to_string(path)?)
+            .map_err(|err| anyhow::anyhow!("Invalid game state in {}: {err}", path.display()))?;
+        saved
+            .validate()
+            .with_context(|| format!("Invalid game state in {}", path.display()))?;
+
+        Ok(saved.into())
+    }
+
+    /// Saves the state; writing to a temporary file first so an interrupted save leaves the old state intact
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let mut temp_path = path.as_os_str().to_owned();
+        temp_path.push(".tmp");
+        fs::write(
+            &temp_path,
+            serde_json::to_string_pretty(&SavedGame::from(self))?,
+        )?;
+        fs::rename(temp_path, path)?;
+
+        Ok(())
+    }
+}
+
+/// A game as it is written to disk.
+///
+/// Boards only keep their spaces and result; the counts of marked spaces a [BingoBoard] plays with are
+/// rebuilt from the spaces when the game is loaded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedGame {
+    version: u32,
+    draws: Vec<u32>,
+    cursor: usize,
+    placement: usize,
+    patterns: Vec<WinPattern>,
+    boards: Vec<SavedBoard>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedBoard {
+    spaces: Vec<Vec<BingoSpace>>,
+    /// Set once the board has won
+    result: Option<SavedResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct SavedResult {
+    winning_val: u32,
+    winning_pattern: WinPattern,
+    placement: usize,
+}
+
+impl SavedGame {
+    /// Checks that the game can be resumed: every board must be rectangular, have marked exactly the spaces
+    /// drawn before it won, and have won on a drawn value with a placement no other board has
+    fn validate(&self) -> Result<(), StateError> {
+        if self.version != STATE_VERSION {
+            return Err(StateError::Version {
+                found: self.version,
+            });
+        }
+        if self.cursor > self.draws.len() {
+            return Err(StateError::Cursor {
+                cursor: self.cursor,
+                draws: self.draws.len(),
+            });
+        }
+
+        // The turn each value was first drawn on; drawing a value again marks nothing new
+        let mut drawn_on = HashMap::new();
+        for (turn, &val) in self.draws[..self.cursor].iter().enumerate() {
+            drawn_on.entry(val).or_insert(turn);
+        }
+
+        let mut placements = vec![];
+        for (board_idx, board) in self.boards.iter().enumerate() {
+            let height = board.spaces.len();
+            let width = board.spaces.first().map_or(0, |row| row.len());
+            if board.spaces.iter().any(|row| row.len() != width) {
+                return Err(StateError::BoardShape {
+                    board: board_idx,
+                    width,
+                    height,
+                });
+            }
+
+            let last_turn = match &board.result {
+                Some(result) => {
+                    let won_on = drawn_on.get(&result.winning_val);
+                    if won_on.is_none() || !self.patterns.contains(&result.winning_pattern) {
+                        return Err(StateError::BoardResult { board: board_idx });
+                    }
+                    placements.push(result.placement);
+                    won_on.copied()
+                }
+                None => None,
+            };
+            let should_be_marked = |space: &BingoSpace| {
+                drawn_on
+                    .get(&space.value)
+                    .is_some_and(|&turn| last_turn.is_none_or(|last_turn| turn <= last_turn))
+            };
+            if board
+                .spaces
+                .iter()
+                .flatten()
+                .any(|space| space.marked != should_be_marked(space))
+            {
+                return Err(StateError::BoardMarks { board: board_idx });
+            }
+        }
+
+        placements.sort_unstable();
+        if !placements.iter().copied().eq(0..self.placement) {
+            return Err(StateError::Placements {
+                placement: self.placement,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+impl From<&GameState> for SavedGame {
+    fn from(state: &GameState) -> Self {
+        Self {
+            version: STATE_VERSION,
+            draws: state.draws.clone(),
+            cursor: state.cursor,
+            placement: state.placement,
+            patterns: state.patterns.clone(),
+            boards: state.boards.iter().map(SavedBoard::from).collect(),
+        }
+    }
+}
+
+impl From<SavedGame> for GameState {
+    fn from(saved: SavedGame) -> Self {
+        Self {
+            draws: saved.draws,
+            cursor: saved.cursor,
+            placement: saved.placement,
+            patterns: saved.patterns,
+            boards: saved.boards.into_iter().map(BingoBoard::from).collect(),
+        }
+    }
+}
+
+impl From<&BingoBoard> for SavedBoard {
+    fn from(board: &BingoBoard) -> Self {
+        let result = match (board.winning_val, &board.winning_pattern, board.placement) {
+            (Some(winning_val), Some(winning_pattern), Some(placement)) => Some(SavedResult {
+                winning_val,
+                winning_pattern: winning_pattern.clone(),
+                placement,
+            }),
+            _ => None,
+        };
+
+        Self {
+            spaces: board.spaces.clone(),
+            result,
+        }
+    }
+}
+
+impl From<SavedBoard> for BingoBoard {
+    fn from(saved: SavedBoard) -> Self {
+        let values: Vec<Vec<u32>> = saved
+            .spaces
+            .iter()
+            .map(|row| row.iter().map(|space| space.value).collect())
+            .collect();
+        let mut board = BingoBoard::new(&values);
+        for (y, row) in saved.spaces.iter().enumerate() {
+            for (x, space) in row.iter().enumerate() {
+                if space.marked {
+                    board.mark_space(y, x);
+                }
+            }
+        }
+        if let Some(result) = saved.result {
+            board.is_winner = true;
+            board.winning_val = Some(result.winning_val);
+            board.winning_pattern = Some(result.winning_pattern);
+            board.placement = Some(result.placement);
+        }
+
+        board
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::env;
+
+    use super::*;
+    use crate::{load_input_data, run_game_scan};
+
+    const TEST_INPUT: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1\n\n\
+                              22 13 17 11  0\n 8  2 23  4 24\n21  9 14 16  7\n 6 10  3 18  5\n 1 12 20 15 19\n\n\
+                               3 15  0  2 22\n 9 18 13 17  5\n19  8  7 25 23\n20 11 10 24  4\n14 21 16 12  6\n\n\
+                              14 21 17 24  4\n10 16 15  9 19\n18  8 23 26 20\n22 11 13  6  5\n 2  0 12  3  7";
+
+    fn new_state() -> GameState {
+        let (values, boards) = load_input_data(TEST_INPUT, None).unwrap();
+        GameState::new(values, boards, WinPattern::DEFAULT.to_vec())
+    }
+
+    #[test]
+    fn test_draw_in_sessions() {
+        let mut state = new_state();
+        assert!(state.draw(11).is_empty());
+        assert_eq!(state.draw(1), vec![2]);
+        assert_eq!(state.remaining().len(), 15);
+        assert_eq!(state.draw(100), vec![0, 1]);
+        assert!(state.remaining().is_empty());
+        assert!(state.draw(1).is_empty());
+
+        let (values, mut boards) = load_input_data(TEST_INPUT, None).unwrap();
+        run_game_scan(&values, &mut boards, &WinPattern::DEFAULT);
+        assert_eq!(state.boards, boards);
+        assert_eq!(state.placement, 3);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let path = env::temp_dir().join(format!("day4-state-{}.json", std::process::id()));
+        // A file named like the state with another extension is left alone
+        let neighbour = path.with_extension("tmp");
+        fs::write(&neighbour, "kept").unwrap();
+        let mut state = new_state();
+        state.draw(12);
+        state.save(&path).unwrap();
+        assert_eq!(fs::read_to_string(&neighbour).unwrap(), "kept");
+        fs::remove_file(&neighbour).unwrap();
+
+        let mut loaded = GameState::load(&path).unwrap();
+        assert_eq!(loaded, state);
+        // Resuming gives the same result as an uninterrupted game
+        loaded.draw(100);
+        let mut uninterrupted = new_state();
+        uninterrupted.draw(100);
+        assert_eq!(loaded, uninterrupted);
+
+        let mut json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        json["version"] = serde_json::json!(STATE_VERSION + 1);
+        fs::write(&path, json.to_string()).unwrap();
+        assert!(GameState::load(&path).is_err());
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    /// Saves `state` with `edit` applied to its JSON and returns why loading it fails
+    fn load_error(state: &GameState, edit: impl Fn(&mut serde_json::Value)) -> StateError {
+        let path = env::temp_dir().join(format!("day4-malformed-{}.json", std::process::id()));
+        let mut json = serde_json::to_value(SavedGame::from(state)).unwrap();
+        edit(&mut json);
+        fs::write(&path, json.to_string()).unwrap();
+        let err = GameState::load(&path)
+            .unwrap_err()
+            .downcast::<StateError>()
+            .unwrap();
+        fs::remove_file(&path).unwrap();
+
+        err
+    }
+
+    #[test]
+    fn test_load_malformed_board() {
+        let mut state = new_state();
+        state.draw(5);
+
+        let short_row = load_error(&state, |json| {
+            json["boards"][1]["spaces"][2].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            short_row,
+            StateError::BoardShape {
+                board: 1,
+                width: 5,
+                height: 5
+            }
+        );
+        let short_first_row = load_error(&state, |json| {
+            json["boards"][0]["spaces"][0].as_array_mut().unwrap().pop();
+        });
+        assert_eq!(
+            short_first_row,
+            StateError::BoardShape {
+                board: 0,
+                width: 4,
+                height: 5
+            }
+        );
+
+        // 14 has not been drawn, while 11 has
+        let undrawn_mark = load_error(&state, |json| {
+            json["boards"][2]["spaces"][0][0]["marked"] = serde_json::json!(true);
+        });
+        assert_eq!(undrawn_mark, StateError::BoardMarks { board: 2 });
+        let missing_mark = load_error(&state, |json| {
+            json["boards"][0]["spaces"][0][3]["marked"] = serde_json::json!(false);
+        });
+        assert_eq!(missing_mark, StateError::BoardMarks { board: 0 });
+
+        let undrawn_win = load_error(&state, |json| {
+            json["boards"][0]["result"] =
+                serde_json::json!({"winning_val": 22, "winning_pattern": "rows", "placement": 0});
+        });
+        assert_eq!(undrawn_win, StateError::BoardResult { board: 0 });
+    }
+
+    #[test]
+    fn test_load_finished_game() {
+        let mut state = new_state();
+        state.draw(100);
+
+        // Board 2 won on 24, before 10 was drawn
+        let marked_after_win = load_error(&state, |json| {
+            json["boards"][2]["spaces"][1][0]["marked"] = serde_json::json!(true);
+        });
+        assert_eq!(marked_after_win, StateError::BoardMarks { board: 2 });
+        let shared_placement = load_error(&state, |json| {
+            json["boards"][1]["result"]["placement"] =
+                json["boards"][0]["result"]["placement"].clone();
+        });
+        assert_eq!(shared_placement, StateError::Placements { placement: 3 });
+        let late_placement = load_error(&state, |json| {
+            json["boards"][1]["result"]["placement"] = serde_json::json!(3);
+        });
+        assert_eq!(late_placement, StateError::Placements { placement: 3 });
+    }
+}
@@ -0,0 +1,198 @@
+use std::{collections::HashMap, fmt::Write};
+
+use serde::Serialize;
+
+use crate::VentLine;
+
+/// A point covered by at least the danger threshold's number of lines
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DangerPoint {
+    pub x: i32,
+    pub y: i32,
+    pub count: u32,
+    /// Indices into the input of the lines covering the point, in input order
+    pub lines: Vec<usize>,
+}
+
+/// Finds the points covered by `threshold` or more lines, ordered by row then column.
+///
+/// The lines' coverage must already be calculated; lines without any are skipped. Each line is given by its
+/// index in the input, so lines left out of the calculation keep their original numbers.
+pub fn danger_points<'a>(
+    vent_lines: impl IntoIterator<Item = (usize, &'a VentLine)>,
+    threshold: u32,
+) -> Vec<DangerPoint> {
+    let mut lines_at: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+    for (line_idx, vent_line) in vent_lines {
+        for &point in vent_line.covered_points.iter().flatten() {
+            lines_at.entry(point).or_default().push(line_idx);
+        }
+    }
+
+    let mut points: Vec<DangerPoint> = lines_at
+        .into_iter()
+        .filter(|(_, lines)| lines.len() as u32 >= threshold)
+        .map(|((x, y), lines)| DangerPoint {
+            x,
+            y,
+            count: lines.len() as u32,
+            lines,
+        })
+        .collect();
+    points.sort_by_key(|point| (point.y, point.x));
+
+    points
+}
+
+/// Formats the danger points one per line as `x,y: count (lines ...)`
+pub fn danger_points_to_text(points: &[DangerPoint]) -> String {
+    let mut out = String::new();
+    for point in points {
+        let lines: Vec<String> = point.lines.iter().map(|line| line.to_string()).collect();
+        // Writing to a `String` cannot fail
+        writeln!(
+            out,
+            "{},{}: {} (lines {})",
+            point.x,
+            point.y,
+            point.count,
+            lines.join(", ")
+        )
+        .unwrap();
+    }
+
+    out
+}
+
+/// Number of points in the grid at one coverage level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct CoverageLevel {
+    pub coverage: u32,
+    pub points: usize,
+}
+
+/// How many points in the grid are covered by each number of lines, from 0 up
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoverageHistogram {
+    pub levels: Vec<CoverageLevel>,
+}
+
+impl CoverageHistogram {
+    /// Counts the coverage levels of a grid of `cells` points, given the coverage of at least every covered
+    /// point; points left out are counted as uncovered
+    pub fn new(counts: impl IntoIterator<Item = u32>, cells: usize) -> Self {
+        let mut points_at: Vec<usize> = vec![0];
+        let mut counted = 0;
+        for count in counts {
+            if count as usize >= points_at.len() {
+                points_at.resize(count as usize + 1, 0);
+            }
+            points_at[count as usize] += 1;
+            counted += 1;
+        }
+        points_at[0] += cells.saturating_sub(counted);
+
+        Self {
+            levels: points_at
+                .into_iter()
+                .enumerate()
+                .filter(|&(coverage, points)| coverage == 0 || points > 0)
+                .map(|(coverage, points)| CoverageLevel {
+                    coverage: coverage as u32,
+                    points,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("coverage,points\n");
+        for level in &self.levels {
+            // Writing to a `String` cannot fail
+            writeln!(out, "{},{}", level.coverage, level.points).unwrap();
+        }
+
+        out
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.levels)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{load_input_data, Rasterization};
+
+    #[test]
+    fn test_danger_points() {
+        let mut vent_lines = load_input_data("0,0 -> 4,0\n2,0 -> 2,3\n4,0 -> 0,0\n0,3 -> 3,0");
+        for vent_line in &mut vent_lines {
+            vent_line.calculate_coverage(false, Rasterization::Exact);
+        }
+
+        let points = danger_points(vent_lines.iter().enumerate(), 3);
+        assert_eq!(
+            points,
+            vec![
+                DangerPoint {
+                    x: 2,
+                    y: 0,
+                    count: 3,
+                    lines: vec![0, 1, 2]
+                },
+                DangerPoint {
+                    x: 3,
+                    y: 0,
+                    count: 3,
+                    lines: vec![0, 2, 3]
+                }
+            ]
+        );
+        assert_eq!(
+            danger_points_to_text(&points),
+            "2,0: 3 (lines 0, 1, 2)\n3,0: 3 (lines 0, 2, 3)\n"
+        );
+
+        // Without the third line, only the lines that remain are listed, under their original indices
+        let without_third = vent_lines
+            .iter()
+            .enumerate()
+            .filter(|&(line_idx, _)| line_idx != 2);
+        let points = danger_points(without_third, 2);
+        let coords: Vec<(i32, i32)> = points.iter().map(|point| (point.x, point.y)).collect();
+        assert_eq!(coords, vec![(2, 0), (3, 0), (2, 1)]);
+        assert_eq!(points[1].lines, vec![0, 3]);
+    }
+
+    #[test]
+    fn test_histogram() {
+        let histogram = CoverageHistogram::new([0, 3, 1, 1, 0, 3], 6);
+        assert_eq!(
+            histogram.levels,
+            vec![
+                CoverageLevel {
+                    coverage: 0,
+                    points: 2
+                },
+                CoverageLevel {
+                    coverage: 1,
+                    points: 2
+                },
+                CoverageLevel {
+                    coverage: 3,
+                    points: 2
+                }
+            ]
+        );
+        assert_eq!(histogram.to_csv(), "coverage,points\n0,2\n1,2\n3,2\n");
+
+        // Only the covered points of a sparse grid are given
+        let sparse = CoverageHistogram::new([3, 1, 1, 3], 100);
+        assert_eq!(sparse.levels[0].points, 96);
+        let json: serde_json::Value = serde_json::from_str(&sparse.to_json().unwrap()).unwrap();
+        assert_eq!(json[2]["coverage"], 3);
+        assert_eq!(json[2]["points"], 2);
+    }
+}
@@ -0,0 +1,247 @@
+use clap::ValueEnum;
+
+use crate::VentLine;
+
+/// Colours a coverage of 0 up to the grid's highest coverage is spread across, evenly spaced
+const COLOUR_RAMP: [(u8, u8, u8); 5] = [
+    (0, 0, 0),
+    (40, 0, 160),
+    (210, 20, 40),
+    (255, 200, 0),
+    (255, 255, 255),
+];
+
+/// Colour the vent lines are drawn with over a PPM heatmap
+const OVERLAY_COLOUR: (u8, u8, u8) = (0, 255, 255);
+
+/// Brightest gray used for coverage in a PGM heatmap with an overlay, leaving white for the lines
+const OVERLAY_GRAY_LIMIT: u8 = 191;
+
+/// Netpbm image formats a heatmap can be written as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum ImageFormat {
+    /// Grayscale, brighter where more lines overlap
+    Pgm,
+    /// Colour, with coverage following a black, blue, red, yellow and white ramp
+    #[default]
+    Ppm,
+}
+
+/// Draws a dense coverage grid as an image, one `scale` by `scale` block of pixels per cell
+pub struct Heatmap<'a> {
+    coverage: &'a [Vec<u32>],
+    /// Point stored in the first cell of `coverage`
+    origin: (i32, i32),
+    scale: usize,
+    /// Lines to draw over the coverage
+    overlay: Option<&'a [VentLine]>,
+}
+
+impl<'a> Heatmap<'a> {
+    pub fn new(coverage: &'a [Vec<u32>], origin: (i32, i32), scale: usize) -> Self {
+        Self {
+            coverage,
+            origin,
+            scale: scale.max(1),
+            overlay: None,
+        }
+    }
+
+    /// Draws each line from the centre of its start cell to the centre of its end cell over the coverage
+    pub fn with_overlay(mut self, vent_lines: &'a [VentLine]) -> Self {
+        self.overlay = Some(vent_lines);
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.coverage.first().map_or(0, |row| row.len()) * self.scale
+    }
+
+    pub fn height(&self) -> usize {
+        self.coverage.len() * self.scale
+    }
+
+    pub fn encode(&self, format: ImageFormat) -> Vec<u8> {
+        match format {
+            ImageFormat::Pgm => self.to_pgm(),
+            ImageFormat::Ppm => self.to_ppm(),
+        }
+    }
+
+    /// Encodes the heatmap as a binary (P5) PGM image
+    pub fn to_pgm(&self) -> Vec<u8> {
+        let limit = if self.overlay.is_some() {
+            OVERLAY_GRAY_LIMIT
+        } else {
+            u8::MAX
+        };
+        let max = self.max_coverage();
+        let gray = |count: u32| (count as u64 * limit as u64 / max as u64) as u8;
+
+        let mut pixels: Vec<u8> = self.pixels().into_iter().map(gray).collect();
+        for (x, y) in self.overlay_pixels() {
+            pixels[y * self.width() + x] = u8::MAX;
+        }
+
+        let mut image = format!("P5\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        image.extend(pixels);
+        image
+    }
+
+    /// Encodes the heatmap as a binary (P6) PPM image
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let max = self.max_coverage();
+
+        let mut pixels: Vec<(u8, u8, u8)> = self
+            .pixels()
+            .into_iter()
+            .map(|count| ramp(count as f64 / max as f64))
+            .collect();
+        for (x, y) in self.overlay_pixels() {
+            pixels[y * self.width() + x] = OVERLAY_COLOUR;
+        }
+
+        let mut image = format!("P6\n{} {}\n255\n", self.width(), self.height()).into_bytes();
+        image.extend(pixels.into_iter().flat_map(|(r, g, b)| [r, g, b]));
+        image
+    }
+
+    /// Highest coverage in the grid; at least 1 so it can always be divided by
+    fn max_coverage(&self) -> u32 {
+        self.coverage
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1)
+    }
+
+    /// The coverage under each pixel, row by row
+    fn pixels(&self) -> Vec<u32> {
+        self.coverage
+            .iter()
+            .flat_map(|row| {
+                let scaled_row: Vec<u32> = row
+                    .iter()
+                    .flat_map(|&count| std::iter::repeat_n(count, self.scale))
+                    .collect();
+                std::iter::repeat_n(scaled_row, self.scale).flatten()
+            })
+            .collect()
+    }
+
+    /// Pixels the overlay's lines pass through, stepping one pixel at a time along each line's major axis
+    fn overlay_pixels(&self) -> Vec<(usize, usize)> {
+        let Some(vent_lines) = self.overlay else {
+            return vec![];
+        };
+        let centre = |(x, y): (i32, i32)| {
+            let half = (self.scale / 2) as i64;
+            (
+                (x - self.origin.0) as i64 * self.scale as i64 + half,
+                (y - self.origin.1) as i64 * self.scale as i64 + half,
+            )
+        };
+
+        let mut pixels = vec![];
+        for vent_line in vent_lines {
+            let (x1, y1) = centre(vent_line.start);
+            let (x2, y2) = centre(vent_line.end);
+            let (dx, dy) = (x2 - x1, y2 - y1);
+            let steps = dx.abs().max(dy.abs()).max(1);
+            for t in 0..=steps {
+                let x = x1 + crate::div_round((t * dx) as i128, steps as i128) as i64;
+                let y = y1 + crate::div_round((t * dy) as i128, steps as i128) as i64;
+                if (0..self.width() as i64).contains(&x) && (0..self.height() as i64).contains(&y) {
+                    pixels.push((x as usize, y as usize));
+                }
+            }
+        }
+
+        pixels
+    }
+}
+
+/// Colour at `t` between 0 and 1 along [COLOUR_RAMP]
+fn ramp(t: f64) -> (u8, u8, u8) {
+    let position = t.clamp(0.0, 1.0) * (COLOUR_RAMP.len() - 1) as f64;
+    let low = (position.floor() as usize).min(COLOUR_RAMP.len() - 2);
+    let fraction = position - low as f64;
+    let (from, to) = (COLOUR_RAMP[low], COLOUR_RAMP[low + 1]);
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * fraction).round() as u8;
+
+    (mix(from.0, to.0), mix(from.1, to.1), mix(from.2, to.2))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn coverage() -> Vec<Vec<u32>> {
+        vec![vec![0, 1, 2], vec![4, 0, 0]]
+    }
+
+    /// Splits an image into its header text and pixel bytes
+    fn split_header(image: &[u8]) -> (String, &[u8]) {
+        let mut newlines = 0;
+        let end = image
+            .iter()
+            .position(|&byte| {
+                newlines += (byte == b'\n') as usize;
+                newlines == 3
+            })
+            .unwrap();
+        (
+            String::from_utf8(image[..=end].to_vec()).unwrap(),
+            &image[end + 1..],
+        )
+    }
+
+    #[test]
+    fn test_pgm() {
+        let coverage = coverage();
+        let image = Heatmap::new(&coverage, (0, 0), 1).to_pgm();
+        let (header, pixels) = split_header(&image);
+
+        assert_eq!(header, "P5\n3 2\n255\n");
+        assert_eq!(pixels, [0, 63, 127, 255, 0, 0]);
+    }
+
+    #[test]
+    fn test_ppm_scaled() {
+        let coverage = coverage();
+        let heatmap = Heatmap::new(&coverage, (0, 0), 3);
+        let image = heatmap.encode(ImageFormat::Ppm);
+        let (header, pixels) = split_header(&image);
+
+        assert_eq!(header, "P6\n9 6\n255\n");
+        assert_eq!(pixels.len(), 9 * 6 * 3);
+        let pixel = |x: usize, y: usize| {
+            let start = (y * 9 + x) * 3;
+            (pixels[start], pixels[start + 1], pixels[start + 2])
+        };
+        assert_eq!(pixel(0, 0), COLOUR_RAMP[0]);
+        // Coverage 2 of 4 is halfway along the ramp
+        assert_eq!(pixel(6, 0), COLOUR_RAMP[2]);
+        assert_eq!(pixel(8, 2), COLOUR_RAMP[2]);
+        assert_eq!(pixel(2, 5), COLOUR_RAMP[4]);
+    }
+
+    #[test]
+    fn test_overlay() {
+        let coverage = vec![vec![0; 4]; 3];
+        let vent_lines = [VentLine::new((-1, 10), (2, 10))];
+        let heatmap = Heatmap::new(&coverage, (-1, 9), 2).with_overlay(&vent_lines);
+        let image = heatmap.to_pgm();
+        let (_, pixels) = split_header(&image);
+
+        // The line runs along the middle of the second row of cells, from the first cell's centre to the last's
+        for y in 0..6 {
+            for x in 0..8 {
+                let on_line = y == 3 && (1..=7).contains(&x);
+                assert_eq!(pixels[y * 8 + x] == u8::MAX, on_line, "pixel ({x}, {y})");
+            }
+        }
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use crate::{Bounds, Rasterization, VentLine};
+
+/// Uniform grid of buckets over a set of vent lines, each bucket holding the lines whose segment passes
+/// through it, so queries only have to check the lines near the point or rectangle asked about
+pub struct LineIndex<'a> {
+    vent_lines: &'a [VentLine],
+    rasterization: Rasterization,
+    /// Width and height of every bucket
+    cell_size: i64,
+    buckets: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Indexes the lines with buckets of `cell_size` by `cell_size` points; point queries use
+    /// `rasterization` to decide which points a line covers
+    pub fn new(vent_lines: &'a [VentLine], rasterization: Rasterization, cell_size: i64) -> Self {
+        let cell_size = cell_size.max(1);
+        let mut buckets: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (line_idx, vent_line) in vent_lines.iter().enumerate() {
+            for bucket in line_buckets(vent_line, cell_size) {
+                buckets.entry(bucket).or_default().push(line_idx);
+            }
+        }
+
+        Self {
+            vent_lines,
+            rasterization,
+            cell_size,
+            buckets,
+        }
+    }
+
+    /// Picks a bucket size giving about one line per bucket row and column across the lines' bounds
+    pub fn with_default_cell_size(
+        vent_lines: &'a [VentLine],
+        rasterization: Rasterization,
+        bounds: Bounds,
+    ) -> Self {
+        let extent = (bounds.x_max as i64 - bounds.x_min as i64)
+            .max(bounds.y_max as i64 - bounds.y_min as i64)
+            + 1;
+        let lines_per_side = (vent_lines.len() as f64).sqrt().ceil().max(1.0) as i64;
+
+        Self::new(
+            vent_lines,
+            rasterization,
+            (extent + lines_per_side - 1) / lines_per_side,
+        )
+    }
+
+    /// Indices of the lines covering `point`, in input order
+    pub fn lines_at(&self, point: (i32, i32)) -> Vec<usize> {
+        let (column, row) = (
+            (point.0 as i64).div_euclid(self.cell_size),
+            (point.1 as i64).div_euclid(self.cell_size),
+        );
+        // Bresenham points can be up to half a point off the segment, possibly over a bucket's edge
+        let buckets = match self.rasterization {
+            Rasterization::Exact => vec![(column, row)],
+            Rasterization::Bresenham => vec![
+                (column, row),
+                (column - 1, row),
+                (column + 1, row),
+                (column, row - 1),
+                (column, row + 1),
+            ],
+        };
+
+        let mut lines: Vec<usize> = buckets
+            .iter()
+            .filter_map(|bucket| self.buckets.get(bucket))
+            .flatten()
+            .copied()
+            .collect();
+        lines.sort_unstable();
+        lines.dedup();
+        lines.retain(|&line_idx| {
+            self.vent_lines[line_idx].intersects_with(point, true, self.rasterization)
+        });
+
+        lines
+    }
+
+    /// Number of lines covering `point`
+    pub fn coverage_at(&self, point: (i32, i32)) -> u32 {
+        self.lines_at(point).len() as u32
+    }
+
+    /// Indices of the lines whose segment touches the rectangle `area`, in input order.
+    ///
+    /// Unlike point queries, this follows each line's exact geometry, so a line passing between lattice
+    /// points still crosses any rectangle it runs through.
+    pub fn lines_in(&self, area: Bounds) -> Vec<usize> {
+        let (column_min, column_max) = (
+            (area.x_min as i64).div_euclid(self.cell_size),
+            (area.x_max as i64).div_euclid(self.cell_size),
+        );
+        let (row_min, row_max) = (
+            (area.y_min as i64).div_euclid(self.cell_size),
+            (area.y_max as i64).div_euclid(self.cell_size),
+        );
+
+        let mut lines: Vec<usize> = vec![];
+        if (column_max - column_min + 1) * (row_max - row_min + 1) > self.buckets.len() as i64 {
+            // Checking every bucket the index has is cheaper than every bucket the rectangle covers
+            for (&(column, row), bucket_lines) in &self.buckets {
+                if (column_min..=column_max).contains(&column) && (row_min..=row_max).contains(&row)
+                {
+                    lines.extend(bucket_lines);
+                }
+            }
+        } else {
+            for column in column_min..=column_max {
+                for row in row_min..=row_max {
+                    lines.extend(self.buckets.get(&(column, row)).into_iter().flatten());
+                }
+            }
+        }
+        lines.sort_unstable();
+        lines.dedup();
+        lines.retain(|&line_idx| segment_touches(&self.vent_lines[line_idx], area));
+
+        lines
+    }
+}
+
+/// Buckets the segment passes through, found one bucket column at a time from the segment's `y` range
+/// within that column
+fn line_buckets(vent_line: &VentLine, cell_size: i64) -> Vec<(i64, i64)> {
+    let (x1, y1) = (vent_line.start.0 as i128, vent_line.start.1 as i128);
+    let (dy, dx) = (vent_line.slope.0 as i128, vent_line.slope.1 as i128);
+    let bucket_of = |value: i128| value.div_euclid(cell_size as i128) as i64;
+
+    let (column_min, column_max) = (
+        bucket_of(vent_line.x_min as i128),
+        bucket_of(vent_line.x_max as i128),
+    );
+    let mut buckets = vec![];
+    for column in column_min..=column_max {
+        let (row_min, row_max) = if dx == 0 {
+            (
+                bucket_of(vent_line.y_min as i128),
+                bucket_of(vent_line.y_max as i128),
+            )
+        } else {
+            // The part of the segment within this column, up to the start of the next one and rounded
+            // outwards to whole points
+            let left = (column as i128 * cell_size as i128).max(vent_line.x_min as i128);
+            let right = ((column as i128 + 1) * cell_size as i128).min(vent_line.x_max as i128);
+            // `y` at `x` is `numerator(x) / dx.abs()`
+            let numerator = |x: i128| (y1 * dx + (x - x1) * dy) * dx.signum();
+            let floor = |x: i128| numerator(x).div_euclid(dx.abs());
+            let ceil = |x: i128| -(-numerator(x)).div_euclid(dx.abs());
+            (
+                bucket_of(floor(left).min(floor(right))),
+                bucket_of(ceil(left).max(ceil(right))),
+            )
+        };
+        for row in row_min..=row_max {
+            buckets.push((column, row));
+        }
+    }
+
+    buckets
+}
+
+/// Whether the segment touches the closed rectangle `area`
+fn segment_touches(vent_line: &VentLine, area: Bounds) -> bool {
+    if vent_line.x_max < area.x_min
+        || vent_line.x_min > area.x_max
+        || vent_line.y_max < area.y_min
+        || vent_line.y_min > area.y_max
+    {
+        return false;
+    }
+
+    // The segment's bounding box overlaps the rectangle, so it touches it unless every corner lies strictly
+    // on the same side of the segment's line
+    let (x1, y1) = (vent_line.start.0 as i128, vent_line.start.1 as i128);
+    let (dy, dx) = (vent_line.slope.0 as i128, vent_line.slope.1 as i128);
+    let side = |(x, y): (i32, i32)| (dx * (y as i128 - y1) - dy * (x as i128 - x1)).signum();
+    let sides = [
+        side((area.x_min, area.y_min)),
+        side((area.x_max, area.y_min)),
+        side((area.x_min, area.y_max)),
+        side((area.x_max, area.y_max)),
+    ];
+
+    !(sides.iter().all(|&side| side > 0) || sides.iter().all(|&side| side < 0))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{load_input_data, VentGrid};
+
+    const EXAMPLE: &str = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n\
+                           6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+
+    fn rect(x_min: i32, y_min: i32, x_max: i32, y_max: i32) -> Bounds {
+        Bounds {
+            x_min,
+            y_min,
+            x_max,
+            y_max,
+        }
+    }
+
+    #[test]
+    fn test_point_queries_match_coverage() {
+        let mut vent_grid = VentGrid::new(load_input_data(EXAMPLE)).unwrap();
+        let coverage = vent_grid.calculate_coverage_v2(true);
+
+        for cell_size in [1, 3, 4, 100] {
+            let index = LineIndex::new(&vent_grid.vent_lines, Rasterization::Exact, cell_size);
+            for (y, row) in coverage.iter().enumerate() {
+                for (x, &count) in row.iter().enumerate() {
+                    assert_eq!(index.coverage_at((x as i32, y as i32)), count);
+                }
+            }
+            assert_eq!(index.lines_at((4, 4)), vec![1, 2, 8]);
+            assert!(index.lines_at((-3, 20)).is_empty());
+        }
+    }
+
+    #[test]
+    fn test_bresenham_point_queries_match_coverage() {
+        let input = "0,0 -> 9,2\n1,0 -> 4,17\n13,1 -> 0,8\n5,0 -> 6,17\n0,16 -> 13,16";
+        let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+        vent_grid.set_rasterization(Rasterization::Bresenham);
+        let coverage = vent_grid.calculate_coverage_v2(true);
+
+        for cell_size in [1, 2, 5] {
+            let index = LineIndex::new(&vent_grid.vent_lines, Rasterization::Bresenham, cell_size);
+            for (y, row) in coverage.iter().enumerate() {
+                for (x, &count) in row.iter().enumerate() {
+                    assert_eq!(index.coverage_at((x as i32, y as i32)), count, "({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_rectangle_queries() {
+        let vent_lines =
+            load_input_data("0,0 -> 2,10\n-5,-5 -> 5,-5\n20,20 -> 30,25\n-10,10 -> 10,-10");
+
+        for cell_size in [1, 2, 7, 50] {
+            let index = LineIndex::new(&vent_lines, Rasterization::Exact, cell_size);
+            // The steep line runs through (0.2, 1) to (0.4, 2) without covering a point in the rectangle
+            assert_eq!(index.lines_in(rect(0, 1, 1, 2)), vec![0]);
+            assert!(index.lines_in(rect(2, 1, 3, 4)).is_empty());
+            assert_eq!(index.lines_in(rect(-6, -6, 1, 0)), vec![0, 1, 3]);
+            assert_eq!(index.lines_in(rect(21, 20, 22, 21)), vec![2]);
+            assert_eq!(index.lines_in(rect(-100, -100, 100, 100)), vec![0, 1, 2, 3]);
+        }
+    }
+
+    #[test]
+    fn test_default_cell_size() {
+        let vent_lines =
+            load_input_data("-1000,-1000 -> 1000,1000\n-1000,1000 -> 1000,-1000\n0,5 -> 0,-5");
+        let bounds = VentGrid::new(vent_lines.clone()).unwrap().bounds;
+        let index = LineIndex::with_default_cell_size(&vent_lines, Rasterization::Exact, bounds);
+
+        assert_eq!(index.cell_size, 1001);
+        assert_eq!(index.lines_at((0, 0)), vec![0, 1, 2]);
+        assert_eq!(index.lines_at((-999, 999)), vec![1]);
+        assert_eq!(index.lines_in(rect(500, -600, 600, -500)), vec![1]);
+    }
+}
@@ -1,26 +1,70 @@
-use std::{fs, io, path::PathBuf, time::Instant};
+use std::{fs, io, ops::RangeInclusive, path::PathBuf, thread, time::Instant};
 
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
+use danger::{danger_points_to_text, CoverageHistogram, DangerPoint};
+use heatmap::{Heatmap, ImageFormat};
+use index::LineIndex;
+use render::render_coverage;
+use sparse::SparseCoverage;
+
+mod danger;
+mod heatmap;
+mod index;
+mod render;
+mod sparse;
+mod sweep;
+
+#[cfg(test)]
 #[macro_use]
 extern crate lazy_static;
 
+/// How the points covered by an angled [VentLine] are chosen
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum Rasterization {
+    /// Only the lattice points lying exactly on the line
+    #[default]
+    Exact,
+    /// One point per step along the line's major axis, rounded to the nearest cell like Bresenham's
+    /// algorithm; ties round away from the start
+    Bresenham,
+}
+
+/// Greatest common divisor of the magnitudes of `a` and `b`
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a as i64
+}
+
+/// Divides `numerator` by a positive `denominator`, rounding to the nearest integer and halves away from zero
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    if numerator >= 0 {
+        (2 * numerator + denominator) / (2 * denominator)
+    } else {
+        -((-2 * numerator + denominator) / (2 * denominator))
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 struct VentLine {
     start: (i32, i32),
     end: (i32, i32),
-    slope: (i32, i32),
+    /// `(dy, dx)` from `start` to `end`; wider than the coordinates, since a line can span more than `i32::MAX`
+    slope: (i64, i64),
     x_min: i32,
     x_max: i32,
     y_min: i32,
     y_max: i32,
-    covered_points: Option<Vec<(usize, usize)>>,
+    covered_points: Option<Vec<(i32, i32)>>,
 }
 
 impl VentLine {
     pub fn new(start: (i32, i32), end: (i32, i32)) -> Self {
-        let height_delta = end.1 - start.1;
-        let horiz_delta = end.0 - start.0;
+        let height_delta = end.1 as i64 - start.1 as i64;
+        let horiz_delta = end.0 as i64 - start.0 as i64;
 
         let (x_min, x_max) = if start.0 <= end.0 {
             (start.0, end.0)
@@ -57,48 +101,60 @@ impl VentLine {
         !self.is_vertical() && !self.is_horizontal()
     }
 
-    /// Calculates the points covered by the VentLine and stores the results in the struct
-    pub fn calculate_coverage(&mut self, recalculate: bool) {
-        if self.covered_points.is_some() && !recalculate {
-            return;
+    /// The smallest `(x, y)` step between lattice points on the line; `(0, 0)` for a single point
+    fn lattice_step(&self) -> (i64, i64) {
+        let (dy, dx) = self.slope;
+        match gcd(dx, dy) {
+            0 => (0, 0),
+            divisor => (dx / divisor, dy / divisor),
         }
+    }
 
-        let (x1, y1) = self.start;
-        let m = self.slope.0 as f32 / self.slope.1 as f32;
-        let b = (-x1 as f32 * m) + y1 as f32;
+    /// The points on the line with integer coordinates, from `start` to `end`
+    fn lattice_points(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (step_x, step_y) = self.lattice_step();
+        let steps = gcd(self.slope.1, self.slope.0);
+        let (x1, y1) = (self.start.0 as i64, self.start.1 as i64);
 
-        // Handle vertical lines
-        if self.is_vertical() {
-            self.covered_points = Some(
-                (self.y_min..=self.y_max)
-                    .map(|y| (self.start.0 as usize, y as usize))
-                    .collect(),
-            );
-            return;
-        }
+        (0..=steps).map(move |k| ((x1 + k * step_x) as i32, (y1 + k * step_y) as i32))
+    }
 
-        // Handle horizontal lines
-        if self.is_horizontal() {
-            self.covered_points = Some(
-                (self.x_min..=self.x_max)
-                    .map(|x| (x as usize, self.start.1 as usize))
-                    .collect(),
-            );
+    /// One point per cell along the line's major axis, from `start` to `end`
+    fn bresenham_points(&self) -> impl Iterator<Item = (i32, i32)> {
+        let (dy, dx) = (self.slope.0 as i128, self.slope.1 as i128);
+        let steps = dx.abs().max(dy.abs());
+        let (x1, y1) = (self.start.0 as i128, self.start.1 as i128);
+
+        (0..=steps).map(move |t| {
+            if steps == 0 {
+                return (x1 as i32, y1 as i32);
+            }
+            let x = x1 + div_round(t * dx, steps);
+            let y = y1 + div_round(t * dy, steps);
+            (x as i32, y as i32)
+        })
+    }
+
+    /// Calculates the points covered by the VentLine and stores the results in the struct
+    pub fn calculate_coverage(&mut self, recalculate: bool, rasterization: Rasterization) {
+        if self.covered_points.is_some() && !recalculate {
             return;
         }
 
-        // All other lines
-        self.covered_points = Some(
-            (self.x_min..=self.x_max)
-                .map(|x| {
-                    let y = (m * (x as f32) + b).floor() as usize;
-                    (x as usize, y)
-                })
-                .collect(),
-        );
+        // Horizontal, vertical and 45 degree lines cover the same points either way
+        self.covered_points = Some(match rasterization {
+            Rasterization::Exact => self.lattice_points().collect(),
+            Rasterization::Bresenham => self.bresenham_points().collect(),
+        });
     }
 
-    pub fn intersects_with(&self, point: (i32, i32), include_angled: bool) -> bool {
+    /// Checks whether the line covers `point` when rasterized with `rasterization`
+    pub fn intersects_with(
+        &self,
+        point: (i32, i32),
+        include_angled: bool,
+        rasterization: Rasterization,
+    ) -> bool {
         // Handle point being outside of line segment's range
         if point.0 < self.x_min
             || point.0 > self.x_max
@@ -122,47 +178,144 @@ impl VentLine {
             return false;
         }
 
-        /*
-            Start with: y - y1 = m(x - x1)
-            End with y = mx + b
-        */
-        let (x1, y1) = self.start;
-        let m = self.slope.0 as f32 / self.slope.1 as f32;
-        let b = (-x1 as f32 * m) + y1 as f32;
+        let (dy, dx) = (self.slope.0 as i128, self.slope.1 as i128);
+        let (x1, y1) = (self.start.0 as i128, self.start.1 as i128);
+        let (px, py) = (point.0 as i128, point.1 as i128);
+
+        match rasterization {
+            // A lattice point is on the line when it is collinear with the line's end points
+            Rasterization::Exact => (px - x1) * dy == (py - y1) * dx,
+            // Find the step along the major axis that reaches the point, then the cell chosen at that step
+            Rasterization::Bresenham => {
+                let steps = dx.abs().max(dy.abs());
+                if dx.abs() >= dy.abs() {
+                    let t = (px - x1).abs();
+                    py == y1 + div_round(t * dy, steps)
+                } else {
+                    let t = (py - y1).abs();
+                    px == x1 + div_round(t * dx, steps)
+                }
+            }
+        }
+    }
+}
+
+/// Largest number of cells a dense grid is allocated with
+const DENSE_CELL_LIMIT: u64 = 1 << 26;
+
+/// Where coverage counts are stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum CoverageBackend {
+    /// Dense unless the grid would have more than [DENSE_CELL_LIMIT] cells
+    #[default]
+    Auto,
+    /// A `Vec<Vec<u32>>` with a cell for every point in the grid; refused past [DENSE_CELL_LIMIT] cells
+    Dense,
+    /// A count for only the points some line covers
+    Sparse,
+}
+
+/// Smallest rectangle holding a set of points; both corners are inclusive
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Bounds {
+    x_min: i32,
+    y_min: i32,
+    x_max: i32,
+    y_max: i32,
+}
 
-        let y = m * (point.0 as f32) + b;
+impl Bounds {
+    fn of_point((x, y): (i32, i32)) -> Self {
+        Self {
+            x_min: x,
+            y_min: y,
+            x_max: x,
+            y_max: y,
+        }
+    }
 
-        // Check if the computed `y` points to the same cell as the given `y`
-        y.floor() as i32 == point.1
+    /// Grows the bounds to hold `other`
+    fn union(self, other: Self) -> Self {
+        Self {
+            x_min: self.x_min.min(other.x_min),
+            y_min: self.y_min.min(other.y_min),
+            x_max: self.x_max.max(other.x_max),
+            y_max: self.y_max.max(other.y_max),
+        }
     }
 }
 
 struct VentGrid {
     vent_lines: Vec<VentLine>,
+    /// Smallest rectangle holding every vent line
+    bounds: Bounds,
+    /// Point stored in the first cell of a dense grid
+    origin: (i32, i32),
     width: usize,
     height: usize,
+    rasterization: Rasterization,
 }
 
 impl VentGrid {
-    pub fn new(vent_lines: Vec<VentLine>) -> Self {
-        // Compute grid dimensions
-        let width = vent_lines
+    /// Sizes the grid to the smallest rectangle holding every line.
+    ///
+    /// Dense grids start at the rectangle's lowest `x` and `y`, so `coverage[y][x]` is the point
+    /// `(origin.0 + x, origin.1 + y)`; a field far from `(0, 0)` only needs as many cells as it spans.
+    pub fn new(vent_lines: Vec<VentLine>) -> Result<Self, io::Error> {
+        let bounds = vent_lines
             .iter()
-            .max_by(|x, y| x.x_max.cmp(&y.x_max))
-            .unwrap()
-            .x_max as usize
-            + 1;
-        let height = vent_lines
-            .iter()
-            .max_by(|x, y| x.y_max.cmp(&y.y_max))
-            .unwrap()
-            .y_max as usize
-            + 1;
+            .map(|vent_line| {
+                Bounds::of_point(vent_line.start).union(Bounds::of_point(vent_line.end))
+            })
+            .reduce(Bounds::union)
+            .ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, "The input has no vent lines")
+            })?;
+        let origin = (bounds.x_min, bounds.y_min);
 
-        Self {
+        // Compute grid dimensions
+        let width = (bounds.x_max as i64 - origin.0 as i64 + 1) as usize;
+        let height = (bounds.y_max as i64 - origin.1 as i64 + 1) as usize;
+
+        Ok(Self {
             vent_lines,
+            bounds,
+            origin,
             width,
             height,
+            rasterization: Rasterization::default(),
+        })
+    }
+
+    /// Whether a dense grid would have more than [DENSE_CELL_LIMIT] cells
+    fn too_large_for_dense(&self) -> bool {
+        (self.width as u64).saturating_mul(self.height as u64) > DENSE_CELL_LIMIT
+    }
+
+    /// Picks the backend [CoverageBackend::Auto] stands for with this grid's size; refusing a dense grid too
+    /// large to allocate
+    pub fn resolve_backend(&self, backend: CoverageBackend) -> Result<CoverageBackend, io::Error> {
+        match backend {
+            CoverageBackend::Auto if self.too_large_for_dense() => Ok(CoverageBackend::Sparse),
+            CoverageBackend::Auto => Ok(CoverageBackend::Dense),
+            CoverageBackend::Dense if self.too_large_for_dense() => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "A dense {}x{} grid has more than {DENSE_CELL_LIMIT} cells; use --backend sparse or auto",
+                    self.width, self.height
+                ),
+            )),
+            backend => Ok(backend),
+        }
+    }
+
+    /// Changes how angled lines are rasterized; discarding any coverage calculated the other way
+    pub fn set_rasterization(&mut self, rasterization: Rasterization) {
+        if rasterization != self.rasterization {
+            self.rasterization = rasterization;
+            for vent_line in &mut self.vent_lines {
+                vent_line.covered_points = None;
+            }
         }
     }
 
@@ -179,16 +332,19 @@ impl VentGrid {
 
         let mut coverage = vec![vec![0; self.width]; self.height];
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let hits = self
+        for (y, row) in coverage.iter_mut().enumerate() {
+            for (x, cell) in row.iter_mut().enumerate() {
+                *cell = self
                     .vent_lines
                     .iter()
                     .filter(|vent_line| {
-                        vent_line.intersects_with((x as i32, y as i32), include_angled)
+                        vent_line.intersects_with(
+                            (x as i32 + self.origin.0, y as i32 + self.origin.1),
+                            include_angled,
+                            self.rasterization,
+                        )
                     })
                     .count() as u32;
-                coverage[y][x] = hits;
             }
         }
 
@@ -197,33 +353,123 @@ impl VentGrid {
 
     pub fn calculate_coverage_v2(&mut self, include_angled: bool) -> Vec<Vec<u32>> {
         let mut coverage = vec![vec![0; self.width]; self.height];
+        let (x_origin, y_origin) = self.origin;
 
-        self.vent_lines
-            .iter_mut()
-            .filter(|vent_line| {
-                if include_angled {
-                    true
-                } else {
-                    !vent_line.is_angled()
-                }
-            })
-            .for_each(|vent_line| {
-                vent_line.calculate_coverage(false);
-            });
-
-        for vent_line in &self.vent_lines {
-            match &vent_line.covered_points {
-                Some(covered_points) => {
-                    for (x, y) in covered_points {
-                        coverage[*y][*x] += 1;
+        for covered_points in self.covered_points(include_angled) {
+            for (x, y) in covered_points {
+                coverage[(y - y_origin) as usize][(x - x_origin) as usize] += 1;
+            }
+        }
+
+        coverage
+    }
+
+    /// Same as [VentGrid::calculate_coverage_v2], split across `jobs` threads.
+    ///
+    /// Lines are rasterized in batches, one per thread, then each thread counts the points falling in its
+    /// own band of rows. Every cell is only ever written by one thread, so the result is identical to the
+    /// sequential version whatever the number of jobs.
+    pub fn calculate_coverage_parallel(
+        &mut self,
+        include_angled: bool,
+        jobs: usize,
+    ) -> Vec<Vec<u32>> {
+        let mut coverage = vec![vec![0; self.width]; self.height];
+        let (x_origin, y_origin) = self.origin;
+        let rasterization = self.rasterization;
+
+        let batch_size = self.vent_lines.len().div_ceil(jobs).max(1);
+        thread::scope(|scope| {
+            for batch in self.vent_lines.chunks_mut(batch_size) {
+                scope.spawn(move || {
+                    batch
+                        .iter_mut()
+                        .filter(|vent_line| include_angled || !vent_line.is_angled())
+                        .for_each(|vent_line| vent_line.calculate_coverage(false, rasterization));
+                });
+            }
+        });
+
+        let vent_lines = &self.vent_lines;
+        let band_size = self.height.div_ceil(jobs).max(1);
+        thread::scope(|scope| {
+            for (band_idx, band) in coverage.chunks_mut(band_size).enumerate() {
+                let band_start = y_origin as i64 + (band_idx * band_size) as i64;
+                let band_end = band_start + band.len() as i64;
+                scope.spawn(move || {
+                    let lines = vent_lines.iter().filter(|vent_line| {
+                        (include_angled || !vent_line.is_angled())
+                            && (vent_line.y_max as i64) >= band_start
+                            && (vent_line.y_min as i64) < band_end
+                    });
+                    for vent_line in lines {
+                        for &(x, y) in vent_line.covered_points.iter().flatten() {
+                            if (band_start..band_end).contains(&(y as i64)) {
+                                band[(y as i64 - band_start) as usize][(x - x_origin) as usize] +=
+                                    1;
+                            }
+                        }
                     }
-                }
-                None => {}
+                });
+            }
+        });
+
+        coverage
+    }
+
+    /// Same as [VentGrid::calculate_coverage_v2], but only storing the points that are covered
+    pub fn calculate_sparse_coverage(&mut self, include_angled: bool) -> SparseCoverage {
+        let mut coverage = SparseCoverage::default();
+
+        for covered_points in self.covered_points(include_angled) {
+            for &point in covered_points {
+                coverage.add(point);
             }
         }
 
         coverage
     }
+
+    /// Builds a [LineIndex] over the lines to answer point and rectangle queries
+    pub fn line_index(&self) -> LineIndex<'_> {
+        LineIndex::with_default_cell_size(&self.vent_lines, self.rasterization, self.bounds)
+    }
+
+    /// Lists the points covered by at least `threshold` lines, with the lines responsible; see
+    /// [danger::danger_points]
+    pub fn danger_points(&mut self, include_angled: bool, threshold: u32) -> Vec<DangerPoint> {
+        self.covered_points(include_angled).for_each(drop);
+        danger::danger_points(
+            self.vent_lines
+                .iter()
+                .enumerate()
+                .filter(|(_, vent_line)| include_angled || !vent_line.is_angled()),
+            threshold,
+        )
+    }
+
+    /// Counts the points covered by two or more lines without allocating any coverage; see
+    /// [sweep::count_overlaps]
+    pub fn count_overlaps(&self, include_angled: bool) -> u64 {
+        sweep::count_overlaps(
+            self.vent_lines
+                .iter()
+                .filter(|vent_line| include_angled || !vent_line.is_angled()),
+        )
+    }
+
+    /// Calculates any missing coverage and returns the points covered by each line taken into account
+    fn covered_points(&mut self, include_angled: bool) -> impl Iterator<Item = &Vec<(i32, i32)>> {
+        let rasterization = self.rasterization;
+
+        self.vent_lines
+            .iter_mut()
+            .filter(move |vent_line| include_angled || !vent_line.is_angled())
+            .map(move |vent_line| {
+                vent_line.calculate_coverage(false, rasterization);
+                &*vent_line.covered_points.get_or_insert_with(Vec::new)
+            })
+    }
 }
 
 fn load_input_data(input: &str) -> Vec<VentLine> {
@@ -248,22 +494,276 @@ fn load_input_data(input: &str) -> Vec<VentLine> {
         .collect()
 }
 
-fn calculate_danger_score(coverage_grid: &Vec<Vec<u32>>) -> usize {
+/// Number of lines that must overlap at a point for it to be dangerous, unless given otherwise
+const DEFAULT_DANGER_THRESHOLD: u32 = 2;
+
+/// Number of points covered by at least `threshold` lines
+fn calculate_danger_score(coverage_grid: &[Vec<u32>], threshold: u32) -> usize {
     coverage_grid
         .iter()
         .flatten()
-        .filter(|&value| *value >= 2)
+        .filter(|&value| *value >= threshold)
         .count()
 }
 
+/// Coverage from whichever backend was used
+enum Coverage {
+    Dense(Vec<Vec<u32>>),
+    Sparse(SparseCoverage),
+    /// Only the points covered by two or more lines, found with `--sweep`
+    Overlaps(u64),
+}
+
+impl Coverage {
+    /// Number of points covered by at least `threshold` lines; overlaps from `--sweep` always use the default
+    /// threshold
+    fn danger_score(&self, threshold: u32) -> usize {
+        match self {
+            Coverage::Dense(coverage_grid) => calculate_danger_score(coverage_grid, threshold),
+            Coverage::Sparse(coverage) => coverage.danger_score(threshold),
+            Coverage::Overlaps(count) => *count as usize,
+        }
+    }
+
+    /// Coverage levels across the grid's `cells` points; `None` for overlaps from `--sweep`
+    fn histogram(&self, cells: usize) -> Option<CoverageHistogram> {
+        match self {
+            Coverage::Dense(coverage_grid) => Some(CoverageHistogram::new(
+                coverage_grid.iter().flatten().copied(),
+                cells,
+            )),
+            Coverage::Sparse(coverage) => Some(CoverageHistogram::new(coverage.counts(), cells)),
+            Coverage::Overlaps(_) => None,
+        }
+    }
+}
+
+fn calculate_coverage(
+    vent_grid: &mut VentGrid,
+    include_angled: bool,
+    args: &Args,
+) -> Result<Coverage, io::Error> {
+    if args.sweep {
+        return Ok(Coverage::Overlaps(vent_grid.count_overlaps(include_angled)));
+    }
+
+    Ok(match vent_grid.resolve_backend(args.backend)? {
+        CoverageBackend::Sparse => {
+            Coverage::Sparse(vent_grid.calculate_sparse_coverage(include_angled))
+        }
+        _ if args.use_v1 => Coverage::Dense(vent_grid.calculate_coverage(include_angled)),
+        _ => match resolve_jobs(args.jobs) {
+            1 => Coverage::Dense(vent_grid.calculate_coverage_v2(include_angled)),
+            jobs => Coverage::Dense(vent_grid.calculate_coverage_parallel(include_angled, jobs)),
+        },
+    })
+}
+
+/// Number of threads to use; every available thread unless fewer are asked for
+fn resolve_jobs(jobs: Option<usize>) -> usize {
+    let available_threads = thread::available_parallelism().map_or(1, |threads| threads.get());
+    match jobs {
+        Some(jobs) => {
+            if jobs == 0 || jobs > available_threads {
+                available_threads
+            } else {
+                jobs
+            }
+        }
+        None => available_threads,
+    }
+}
+
 #[derive(Parser)]
 #[command(author, version)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(long, default_value = "false")]
     use_v1: bool,
 
     #[arg(long, short, default_value = "input.txt")]
     input_path: PathBuf,
+
+    /// How to choose the points covered by angled lines
+    #[arg(long, value_enum, default_value_t = Rasterization::Exact)]
+    rasterization: Rasterization,
+
+    /// How to store coverage; `auto` switches to sparse for grids too large to allocate
+    #[arg(long, value_enum, default_value_t = CoverageBackend::Auto)]
+    backend: CoverageBackend,
+
+    /// Count overlapping points straight from the lines' geometry, without any coverage grid
+    #[arg(long, conflicts_with_all = ["use_v1", "backend", "rasterization"])]
+    sweep: bool,
+
+    /// Number of threads to calculate dense coverage with; defaults to every available thread
+    #[arg(short, long, conflicts_with_all = ["use_v1", "sweep"])]
+    jobs: Option<usize>,
+
+    /// Write an image of the coverage with angles to this path
+    #[arg(long, value_name = "PATH", conflicts_with = "sweep")]
+    heatmap: Option<PathBuf>,
+
+    /// Image format of the heatmap
+    #[arg(long, value_enum, default_value_t = ImageFormat::Ppm, requires = "heatmap")]
+    heatmap_format: ImageFormat,
+
+    /// Width and height in pixels of each grid cell in the heatmap
+    #[arg(long, default_value_t = 1, requires = "heatmap")]
+    scale: usize,
+
+    /// Draw the vent lines over the heatmap
+    #[arg(long, requires = "heatmap")]
+    overlay: bool,
+
+    /// Print the coverage as the puzzle draws it: `.` for none, the count up to 9, then `+`
+    #[arg(long, default_value = "false", conflicts_with = "sweep")]
+    show_grid: bool,
+
+    /// Columns of the grid to print, as `MIN-MAX`; defaults to the whole grid
+    #[arg(long, value_parser = parse_coord_range, allow_hyphen_values = true, requires = "show_grid")]
+    x_range: Option<RangeInclusive<i32>>,
+
+    /// Rows of the grid to print, as `MIN-MAX`; defaults to the whole grid
+    #[arg(long, value_parser = parse_coord_range, allow_hyphen_values = true, requires = "show_grid")]
+    y_range: Option<RangeInclusive<i32>>,
+
+    /// Number of overlapping lines that makes a point dangerous
+    #[arg(short, long, default_value_t = DEFAULT_DANGER_THRESHOLD, conflicts_with = "sweep")]
+    threshold: u32,
+
+    /// List every dangerous point with angles, its overlap count and the indices of the lines covering it
+    #[arg(long, default_value = "false", conflicts_with = "sweep")]
+    list_danger: bool,
+
+    /// Write how many points have each coverage level, with angles, to this path
+    #[arg(long, value_name = "PATH", conflicts_with = "sweep")]
+    histogram: Option<PathBuf>,
+
+    /// Format of the histogram
+    #[arg(long, value_enum, default_value_t = ExportFormat::Json, requires = "histogram")]
+    histogram_format: ExportFormat,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Look up the lines at points and crossing rectangles, angled lines included
+    Query {
+        /// Point to list the covering lines and coverage of, as `X,Y`
+        #[arg(short, long = "point", value_parser = parse_point, allow_hyphen_values = true)]
+        points: Vec<(i32, i32)>,
+
+        /// Rectangle to list the crossing lines of, as opposite corners `X1,Y1,X2,Y2`
+        #[arg(short, long = "rect", value_parser = parse_rect, allow_hyphen_values = true)]
+        rects: Vec<Bounds>,
+    },
+}
+
+/// Parses a point given as `X,Y`
+fn parse_point(point: &str) -> Result<(i32, i32), String> {
+    let (x, y) = point
+        .split_once(',')
+        .ok_or_else(|| format!("invalid point '{point}'; expected X,Y"))?;
+    let parse_coord = |coord: &str| {
+        coord
+            .trim()
+            .parse::<i32>()
+            .map_err(|err| format!("invalid coordinate '{coord}': {err}"))
+    };
+
+    Ok((parse_coord(x)?, parse_coord(y)?))
+}
+
+/// Parses a rectangle given by two opposite corners as `X1,Y1,X2,Y2`
+fn parse_rect(rect: &str) -> Result<Bounds, String> {
+    let coords: Vec<&str> = rect.split(',').collect();
+    let [x1, y1, x2, y2] = coords[..] else {
+        return Err(format!("invalid rectangle '{rect}'; expected X1,Y1,X2,Y2"));
+    };
+    let first = parse_point(&format!("{x1},{y1}"))?;
+    let second = parse_point(&format!("{x2},{y2}"))?;
+
+    Ok(Bounds::of_point(first).union(Bounds::of_point(second)))
+}
+
+/// Answers the `query` subcommand
+fn print_queries(vent_grid: &VentGrid, points: &[(i32, i32)], rects: &[Bounds]) {
+    let index = vent_grid.line_index();
+    let format_lines = |lines: &[usize]| {
+        let lines: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        format!("[{}]", lines.join(", "))
+    };
+
+    for &(x, y) in points {
+        println!(
+            "{x},{y}: coverage {}, lines {}",
+            index.coverage_at((x, y)),
+            format_lines(&index.lines_at((x, y)))
+        );
+    }
+    for rect in rects {
+        println!(
+            "{},{} to {},{}: lines {}",
+            rect.x_min,
+            rect.y_min,
+            rect.x_max,
+            rect.y_max,
+            format_lines(&index.lines_in(*rect))
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+/// Parses an inclusive range of coordinates given as `MIN-MAX`, where either bound may be negative
+fn parse_coord_range(range: &str) -> Result<RangeInclusive<i32>, String> {
+    let (min, max) = range
+        .char_indices()
+        .skip(1)
+        .find(|&(_, c)| c == '-')
+        .map(|(idx, _)| (&range[..idx], &range[idx + 1..]))
+        .ok_or_else(|| format!("invalid range '{range}'; expected MIN-MAX"))?;
+    let parse_bound = |bound: &str| {
+        bound
+            .trim()
+            .parse::<i32>()
+            .map_err(|err| format!("invalid range bound '{bound}': {err}"))
+    };
+    let (min, max) = (parse_bound(min)?, parse_bound(max)?);
+    if min > max {
+        return Err(format!(
+            "range minimum {min} is greater than its maximum {max}"
+        ));
+    }
+
+    Ok(min..=max)
+}
+
+/// Prints the coverage for `--show-grid`
+fn print_grid(coverage: &Coverage, origin: (i32, i32), args: &Args) -> Result<(), io::Error> {
+    let Coverage::Dense(coverage_grid) = coverage else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "--show-grid needs dense coverage; the grid is too large or --backend sparse was given",
+        ));
+    };
+    print!(
+        "{}",
+        render_coverage(
+            coverage_grid,
+            origin,
+            args.x_range.clone(),
+            args.y_range.clone()
+        )
+    );
+
+    Ok(())
 }
 
 fn main() -> Result<(), io::Error> {
@@ -271,33 +771,50 @@ fn main() -> Result<(), io::Error> {
 
     println!("Loading input...");
     let mut start_time = Instant::now();
-    let input = fs::read_to_string(args.input_path)?;
-    let mut vent_grid = VentGrid::new(load_input_data(&input));
+    let input = fs::read_to_string(&args.input_path)?;
+    let mut vent_grid = VentGrid::new(load_input_data(&input))?;
+    vent_grid.set_rasterization(args.rasterization);
     drop(input);
     let mut elapsed = Instant::now() - start_time;
     println!("done ({}ms)\n", elapsed.as_millis());
 
+    if let Some(Command::Query { points, rects }) = &args.command {
+        print_queries(&vent_grid, points, rects);
+        return Ok(());
+    }
+
+    if !args.sweep && vent_grid.resolve_backend(args.backend)? == CoverageBackend::Sparse {
+        if args.use_v1 {
+            eprintln!("warning: --use-v1 only applies to the dense backend");
+        }
+        let bounds = vent_grid.bounds;
+        println!(
+            "Using sparse coverage for ({}, {}) to ({}, {})\n",
+            bounds.x_min, bounds.y_min, bounds.x_max, bounds.y_max
+        );
+    }
+
     // ==============================
     // Part 1 - No angles calculation
     // ==============================
 
     println!("Calculating coverage (no angles)...");
     start_time = Instant::now();
-    let coverage_grid = if args.use_v1 {
-        vent_grid.calculate_coverage(false)
-    } else {
-        vent_grid.calculate_coverage_v2(false)
-    };
+    let coverage = calculate_coverage(&mut vent_grid, false, &args)?;
     elapsed = Instant::now() - start_time;
     println!("done ({}ms)\n", elapsed.as_millis());
 
     println!("Calculating danger score (no angles)...");
     start_time = Instant::now();
-    let danger_score = calculate_danger_score(&coverage_grid);
+    let danger_score = coverage.danger_score(args.threshold);
     elapsed = Instant::now() - start_time;
     println!("done ({}ms)\n", elapsed.as_millis());
 
-    println!("Danger score (no angles): {danger_score}\n\n");
+    println!("Danger score (no angles): {danger_score}\n");
+    if args.show_grid {
+        print_grid(&coverage, vent_grid.origin, &args)?;
+    }
+    println!("\n");
 
     // ==============================
     // Part 2 - With angles calculation
@@ -305,21 +822,65 @@ fn main() -> Result<(), io::Error> {
 
     println!("Calculating coverage (with angles)...");
     start_time = Instant::now();
-    let coverage_grid = if args.use_v1 {
-        vent_grid.calculate_coverage(true)
-    } else {
-        vent_grid.calculate_coverage_v2(true)
-    };
+    let coverage = calculate_coverage(&mut vent_grid, true, &args)?;
     elapsed = Instant::now() - start_time;
     println!("done ({}ms)\n", elapsed.as_millis());
 
     println!("Calculating danger score (with angles)...");
     start_time = Instant::now();
-    let danger_score = calculate_danger_score(&coverage_grid);
+    let danger_score = coverage.danger_score(args.threshold);
     elapsed = Instant::now() - start_time;
     println!("done ({}ms)\n", elapsed.as_millis());
 
     println!("Danger score (with angles): {danger_score}");
+    if args.show_grid {
+        println!();
+        print_grid(&coverage, vent_grid.origin, &args)?;
+    }
+
+    if args.list_danger {
+        println!();
+        print!(
+            "{}",
+            danger_points_to_text(&vent_grid.danger_points(true, args.threshold))
+        );
+    }
+
+    if let Some(histogram_path) = &args.histogram {
+        // Only `--sweep` gives no histogram, and it cannot be combined with `--histogram`
+        let histogram = coverage
+            .histogram(vent_grid.width * vent_grid.height)
+            .unwrap();
+        let contents = match args.histogram_format {
+            ExportFormat::Csv => histogram.to_csv(),
+            ExportFormat::Json => histogram.to_json()?,
+        };
+        fs::write(histogram_path, contents)?;
+        println!(
+            "\nWrote the coverage histogram to {}",
+            histogram_path.display()
+        );
+    }
+
+    if let Some(heatmap_path) = &args.heatmap {
+        let Coverage::Dense(coverage_grid) = &coverage else {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "A heatmap needs dense coverage; the grid is too large or --backend sparse was given",
+            ));
+        };
+        let mut heatmap = Heatmap::new(coverage_grid, vent_grid.origin, args.scale);
+        if args.overlay {
+            heatmap = heatmap.with_overlay(&vent_grid.vent_lines);
+        }
+        fs::write(heatmap_path, heatmap.encode(args.heatmap_format))?;
+        println!(
+            "\nWrote a {}x{} heatmap to {}",
+            heatmap.width(),
+            heatmap.height(),
+            heatmap_path.display()
+        );
+    }
 
     Ok(())
 }
@@ -333,7 +894,7 @@ mod test {
         static ref HORIZONTAL_VENT_LINE: VentLine = VentLine::new((3, 2), (8, 2));
     }
 
-    const TEST_INPUT_DATA_FULL: &'static str = "0,9 -> 5,9\n\
+    const TEST_INPUT_DATA_FULL: &str = "0,9 -> 5,9\n\
                                                 8,0 -> 0,8\n\
                                                 9,4 -> 3,4\n\
                                                 2,2 -> 2,1\n\
@@ -344,7 +905,7 @@ mod test {
                                                 0,0 -> 8,8\n\
                                                 5,5 -> 8,2";
 
-    const TEST_INPUT_DATA_PARTIAL: &'static str = "0,9 -> 5,9";
+    const TEST_INPUT_DATA_PARTIAL: &str = "0,9 -> 5,9";
 
     const COVERAGE_NO_ANGLES: [[u32; 10]; 10] = [
         [0, 0, 0, 0, 0, 0, 0, 1, 0, 0],
@@ -398,7 +959,10 @@ mod test {
         let test_data = [((1, 1), false), ((2, 6), true), ((2, 9), false)];
 
         for (point, expected) in test_data {
-            assert_eq!(VERTICAL_VENT_LINE.intersects_with(point, false), expected);
+            assert_eq!(
+                VERTICAL_VENT_LINE.intersects_with(point, false, Rasterization::Exact),
+                expected
+            );
         }
     }
 
@@ -407,7 +971,10 @@ mod test {
         let test_data = [((1, 1), false), ((5, 2), true), ((2, 9), false)];
 
         for (point, expected) in test_data {
-            assert_eq!(HORIZONTAL_VENT_LINE.intersects_with(point, false), expected);
+            assert_eq!(
+                HORIZONTAL_VENT_LINE.intersects_with(point, false, Rasterization::Exact),
+                expected
+            );
         }
     }
 
@@ -418,7 +985,7 @@ mod test {
 
         for (point, expected) in test_data {
             assert_eq!(
-                vent_line.intersects_with(point, true),
+                vent_line.intersects_with(point, true, Rasterization::Exact),
                 expected,
                 "expected '{expected}' when point is ({}, {})",
                 point.0,
@@ -438,7 +1005,7 @@ mod test {
     #[test]
     fn test_new_vent_grid() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let vent_grid = VentGrid::new(vent_lines);
+        let vent_grid = VentGrid::new(vent_lines).unwrap();
 
         assert_eq!(vent_grid.width, 10);
         assert_eq!(vent_grid.height, 10);
@@ -447,7 +1014,7 @@ mod test {
     #[test]
     fn test_vent_grid_coverage_no_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let vent_grid = VentGrid::new(vent_lines);
+        let vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage(false);
         assert_eq!(coverage, COVERAGE_NO_ANGLES);
@@ -456,7 +1023,7 @@ mod test {
     #[test]
     fn test_vent_grid_coverage_v2_no_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let mut vent_grid = VentGrid::new(vent_lines);
+        let mut vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage_v2(false);
         assert_eq!(coverage, COVERAGE_NO_ANGLES);
@@ -465,7 +1032,7 @@ mod test {
     #[test]
     fn test_vent_grid_coverage_with_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let vent_grid = VentGrid::new(vent_lines);
+        let vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage(true);
         assert_eq!(coverage, COVERAGE_WITH_ANGLES);
@@ -474,7 +1041,7 @@ mod test {
     #[test]
     fn test_vent_grid_coverage_v2_with_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let mut vent_grid = VentGrid::new(vent_lines);
+        let mut vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage_v2(true);
         assert_eq!(coverage, COVERAGE_WITH_ANGLES);
@@ -483,21 +1050,59 @@ mod test {
     #[test]
     fn test_calculate_danger_score_no_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let vent_grid = VentGrid::new(vent_lines);
+        let vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage(false);
 
-        assert_eq!(calculate_danger_score(&coverage), 5);
+        assert_eq!(
+            calculate_danger_score(&coverage, DEFAULT_DANGER_THRESHOLD),
+            5
+        );
     }
 
     #[test]
     fn test_calculate_danger_score_with_angles() {
         let vent_lines = load_input_data(TEST_INPUT_DATA_FULL);
-        let vent_grid = VentGrid::new(vent_lines);
+        let vent_grid = VentGrid::new(vent_lines).unwrap();
 
         let coverage = vent_grid.calculate_coverage(true);
 
-        assert_eq!(calculate_danger_score(&coverage), 12);
+        assert_eq!(
+            calculate_danger_score(&coverage, DEFAULT_DANGER_THRESHOLD),
+            12
+        );
+        assert_eq!(calculate_danger_score(&coverage, 3), 2);
+        assert_eq!(calculate_danger_score(&coverage, 1), 39);
+    }
+
+    #[test]
+    fn test_vent_grid_danger_points() {
+        let mut vent_grid = VentGrid::new(load_input_data(TEST_INPUT_DATA_FULL)).unwrap();
+
+        assert_eq!(
+            vent_grid
+                .danger_points(false, DEFAULT_DANGER_THRESHOLD)
+                .len(),
+            5
+        );
+        let points = vent_grid.danger_points(true, 3);
+        assert_eq!(
+            points,
+            vec![
+                DangerPoint {
+                    x: 4,
+                    y: 4,
+                    count: 3,
+                    lines: vec![1, 2, 8]
+                },
+                DangerPoint {
+                    x: 6,
+                    y: 4,
+                    count: 3,
+                    lines: vec![2, 5, 9]
+                }
+            ]
+        );
     }
 
     #[test]
@@ -518,8 +1123,248 @@ mod test {
         ];
 
         for (mut vent_line, expected_coverage) in test_data {
-            vent_line.calculate_coverage(false);
+            vent_line.calculate_coverage(false, Rasterization::Exact);
             assert_eq!(vent_line.covered_points, Some(expected_coverage));
         }
     }
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(12, -18), 6);
+        assert_eq!(gcd(0, 7), 7);
+        assert_eq!(gcd(0, 0), 0);
+    }
+
+    #[test]
+    fn test_steep_line_coverage() {
+        let mut vent_line = VentLine::new((0, 0), (2, 10));
+
+        vent_line.calculate_coverage(false, Rasterization::Exact);
+        assert_eq!(
+            vent_line.covered_points,
+            Some(vec![(0, 0), (1, 5), (2, 10)])
+        );
+        assert!(vent_line.intersects_with((1, 5), true, Rasterization::Exact));
+        assert!(!vent_line.intersects_with((1, 4), true, Rasterization::Exact));
+
+        // One point per row, with x rounded to the nearest column
+        vent_line.calculate_coverage(true, Rasterization::Bresenham);
+        let expected_x = [0, 0, 0, 1, 1, 1, 1, 1, 2, 2, 2];
+        let expected: Vec<(i32, i32)> = expected_x.iter().copied().zip(0..=10).collect();
+        assert_eq!(vent_line.covered_points, Some(expected.clone()));
+        for (x, y) in expected {
+            assert!(vent_line.intersects_with((x, y), true, Rasterization::Bresenham));
+        }
+        assert!(!vent_line.intersects_with((1, 2), true, Rasterization::Bresenham));
+    }
+
+    #[test]
+    fn test_large_coordinate_line_coverage() {
+        // f32 cannot represent every integer this large, so a floating point slope skips and repeats cells
+        let start = 1_000_000_000;
+        let mut vent_line = VentLine::new((start, start + 9), (start + 3, start));
+
+        vent_line.calculate_coverage(false, Rasterization::Exact);
+        let expected: Vec<(i32, i32)> = (0..=3).map(|k| (start + k, start + 9 - 3 * k)).collect();
+        assert_eq!(vent_line.covered_points, Some(expected));
+        assert!(vent_line.intersects_with((start + 2, start + 3), true, Rasterization::Exact));
+        assert!(!vent_line.intersects_with((start + 2, start + 4), true, Rasterization::Exact));
+
+        vent_line.calculate_coverage(true, Rasterization::Bresenham);
+        let points = vent_line.covered_points.unwrap();
+        assert_eq!(points.len(), 10);
+        assert_eq!(points[0], (start, start + 9));
+        assert_eq!(points[9], (start + 3, start));
+    }
+
+    #[test]
+    fn test_vent_grid_rasterizations_agree() {
+        let input = "0,0 -> 6,2\n1,3 -> 4,0\n5,0 -> 2,6\n0,5 -> 6,5";
+        for rasterization in [Rasterization::Exact, Rasterization::Bresenham] {
+            let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+            vent_grid.set_rasterization(rasterization);
+            assert_eq!(
+                vent_grid.calculate_coverage(true),
+                vent_grid.calculate_coverage_v2(true)
+            );
+        }
+    }
+
+    #[test]
+    fn test_negative_coordinates() {
+        // The example shifted 5 left and 3 up
+        let shifted: String = TEST_INPUT_DATA_FULL
+            .lines()
+            .map(|line| {
+                let points: Vec<String> = line
+                    .split(" -> ")
+                    .map(|point| {
+                        let (x, y) = point.trim().split_once(',').unwrap();
+                        format!(
+                            "{},{}",
+                            x.parse::<i32>().unwrap() - 5,
+                            y.parse::<i32>().unwrap() - 3
+                        )
+                    })
+                    .collect();
+                points.join(" -> ") + "\n"
+            })
+            .collect();
+        let mut vent_grid = VentGrid::new(load_input_data(&shifted)).unwrap();
+
+        assert_eq!(vent_grid.origin, (-5, -3));
+        assert_eq!((vent_grid.width, vent_grid.height), (10, 10));
+        assert_eq!(vent_grid.calculate_coverage(true), COVERAGE_WITH_ANGLES);
+        assert_eq!(vent_grid.calculate_coverage_v2(true), COVERAGE_WITH_ANGLES);
+
+        let coverage = vent_grid.calculate_sparse_coverage(true);
+        assert_eq!(coverage.danger_score(DEFAULT_DANGER_THRESHOLD), 12);
+        assert_eq!(coverage.get((-1, 1)), 3);
+        assert_eq!(coverage.get((-5, 6)), 2);
+    }
+
+    #[test]
+    fn test_sparse_backend_for_huge_grids() {
+        let input = "-200000,-200000 -> 200000,200000\n\
+                     200000,-200000 -> -200000,200000\n\
+                     -5,0 -> 5,0";
+        let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+
+        assert_eq!(
+            vent_grid.resolve_backend(CoverageBackend::Auto).unwrap(),
+            CoverageBackend::Sparse
+        );
+        // A dense grid this size would not fit in memory, even when asked for
+        assert!(vent_grid.resolve_backend(CoverageBackend::Dense).is_err());
+        let coverage = vent_grid.calculate_sparse_coverage(true);
+        assert_eq!(coverage.danger_score(DEFAULT_DANGER_THRESHOLD), 1);
+        assert_eq!(coverage.get((0, 0)), 3);
+        assert_eq!(
+            vent_grid.bounds,
+            Bounds {
+                x_min: -200_000,
+                y_min: -200_000,
+                x_max: 200_000,
+                y_max: 200_000
+            }
+        );
+
+        let small = VentGrid::new(load_input_data(TEST_INPUT_DATA_FULL)).unwrap();
+        assert_eq!(
+            small.resolve_backend(CoverageBackend::Auto).unwrap(),
+            CoverageBackend::Dense
+        );
+    }
+
+    #[test]
+    fn test_lines_wider_than_i32() {
+        // Every line spans about 4e9 points along one axis, but the angled ones have few lattice points
+        let angled = "-2000000000,0 -> 2000000000,2\n\
+                      0,-2000000000 -> 2,2000000000\n\
+                      -2000000000,2 -> 2000000000,0\n\
+                      2,2000000000 -> -2,-2000000000";
+        let mut vent_grid = VentGrid::new(load_input_data(angled)).unwrap();
+        assert_eq!(vent_grid.vent_lines[0].slope, (2, 4_000_000_000));
+        assert_eq!(
+            vent_grid.resolve_backend(CoverageBackend::Auto).unwrap(),
+            CoverageBackend::Sparse
+        );
+        let coverage = vent_grid.calculate_sparse_coverage(true);
+        assert_eq!(coverage.danger_score(DEFAULT_DANGER_THRESHOLD), 2);
+        assert_eq!(coverage.get((0, 1)), 2);
+        assert_eq!(coverage.get((2, 2_000_000_000)), 2);
+
+        let with_horizontal = format!("{angled}\n-2000000000,0 -> 2000000000,0");
+        let vent_grid = VentGrid::new(load_input_data(&with_horizontal)).unwrap();
+        assert_eq!(vent_grid.count_overlaps(false), 0);
+        // The horizontal line also crosses the angled lines at (-2e9, 0), (0, 0), (1, 0) and (2e9, 0)
+        assert_eq!(vent_grid.count_overlaps(true), 6);
+    }
+
+    #[test]
+    fn test_dense_grid_far_from_origin() {
+        let input = "1000000,1000000 -> 1000009,1000009\n1000009,1000000 -> 1000000,1000009";
+        let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+
+        assert_eq!(vent_grid.origin, (1_000_000, 1_000_000));
+        assert_eq!((vent_grid.width, vent_grid.height), (10, 10));
+        assert_eq!(
+            vent_grid.resolve_backend(CoverageBackend::Dense).unwrap(),
+            CoverageBackend::Dense
+        );
+        let coverage = vent_grid.calculate_coverage_v2(true);
+        assert_eq!(
+            calculate_danger_score(&coverage, DEFAULT_DANGER_THRESHOLD),
+            0
+        );
+        assert_eq!(coverage[0][0], 1);
+        assert_eq!(coverage[9][0], 1);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert!(VentGrid::new(load_input_data("")).is_err());
+    }
+
+    #[test]
+    fn test_sparse_coverage_matches_dense() {
+        let mut vent_grid = VentGrid::new(load_input_data(TEST_INPUT_DATA_FULL)).unwrap();
+        for include_angled in [false, true] {
+            let dense = vent_grid.calculate_coverage_v2(include_angled);
+            let sparse = vent_grid.calculate_sparse_coverage(include_angled);
+            for (y, row) in dense.iter().enumerate() {
+                for (x, &count) in row.iter().enumerate() {
+                    assert_eq!(sparse.get((x as i32, y as i32)), count);
+                }
+            }
+            assert_eq!(
+                sparse.danger_score(DEFAULT_DANGER_THRESHOLD),
+                calculate_danger_score(&dense, DEFAULT_DANGER_THRESHOLD)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parallel_coverage_matches_sequential() {
+        let shifted = "-3,-2 -> 9,10\n-3,10 -> 9,-2\n0,0 -> 0,12\n2,7 -> 8,7\n5,-1 -> 1,11";
+        for input in [TEST_INPUT_DATA_FULL, shifted] {
+            for include_angled in [false, true] {
+                let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+                let expected = vent_grid.calculate_coverage_v2(include_angled);
+                for jobs in [1, 2, 3, 7, 32] {
+                    let mut vent_grid = VentGrid::new(load_input_data(input)).unwrap();
+                    assert_eq!(
+                        vent_grid.calculate_coverage_parallel(include_angled, jobs),
+                        expected
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_coord_range() {
+        assert_eq!(parse_coord_range("3-7"), Ok(3..=7));
+        assert_eq!(parse_coord_range("-5--2"), Ok(-5..=-2));
+        assert_eq!(parse_coord_range("-5-2"), Ok(-5..=2));
+        assert!(parse_coord_range("7-3").is_err());
+        assert!(parse_coord_range("7").is_err());
+        assert!(parse_coord_range("-7").is_err());
+    }
+
+    #[test]
+    fn test_parse_query_args() {
+        assert_eq!(parse_point("-3, 7"), Ok((-3, 7)));
+        assert!(parse_point("3").is_err());
+        assert_eq!(
+            parse_rect("5,-1,-2,4"),
+            Ok(Bounds {
+                x_min: -2,
+                y_min: -1,
+                x_max: 5,
+                y_max: 4
+            })
+        );
+        assert!(parse_rect("1,2,3").is_err());
+    }
 }
@@ -0,0 +1,92 @@
+use std::ops::RangeInclusive;
+
+/// Draws coverage the way the puzzle does: `.` where no line passes, the number of lines up to 9, and `+`
+/// beyond that.
+///
+/// `coverage` starts at `origin`, as returned by [crate::VentGrid::calculate_coverage_v2]. The viewport
+/// defaults to the whole grid along either axis; any part of it outside the grid is drawn as `.`.
+pub fn render_coverage(
+    coverage: &[Vec<u32>],
+    origin: (i32, i32),
+    x_range: Option<RangeInclusive<i32>>,
+    y_range: Option<RangeInclusive<i32>>,
+) -> String {
+    let width = coverage.first().map_or(0, |row| row.len()) as i32;
+    let height = coverage.len() as i32;
+    let x_range = x_range.unwrap_or(origin.0..=origin.0 + width - 1);
+    let y_range = y_range.unwrap_or(origin.1..=origin.1 + height - 1);
+
+    let mut out = String::new();
+    for y in y_range {
+        for x in x_range.clone() {
+            let count = coverage
+                .get((y as i64 - origin.1 as i64) as usize)
+                .and_then(|row| row.get((x as i64 - origin.0 as i64) as usize))
+                .copied()
+                .unwrap_or(0);
+            out.push(match count {
+                0 => '.',
+                1..=9 => char::from_digit(count, 10).unwrap(),
+                _ => '+',
+            });
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{load_input_data, VentGrid};
+
+    const EXAMPLE: &str = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n\
+                           6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+
+    #[test]
+    fn test_render_example() {
+        let mut vent_grid = VentGrid::new(load_input_data(EXAMPLE)).unwrap();
+
+        assert_eq!(
+            render_coverage(&vent_grid.calculate_coverage_v2(false), (0, 0), None, None),
+            ".......1..\n\
+             ..1....1..\n\
+             ..1....1..\n\
+             .......1..\n\
+             .112111211\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             ..........\n\
+             222111....\n"
+        );
+        assert_eq!(
+            render_coverage(&vent_grid.calculate_coverage_v2(true), (0, 0), None, None),
+            "1.1....11.\n\
+             .111...2..\n\
+             ..2.1.111.\n\
+             ...1.2.2..\n\
+             .112313211\n\
+             ...1.2....\n\
+             ..1...1...\n\
+             .1.....1..\n\
+             1.......1.\n\
+             222111....\n"
+        );
+    }
+
+    #[test]
+    fn test_render_viewport() {
+        let coverage = vec![vec![0, 12, 3], vec![9, 10, 1]];
+
+        assert_eq!(
+            render_coverage(&coverage, (-1, -1), None, None),
+            ".+3\n9+1\n"
+        );
+        assert_eq!(
+            render_coverage(&coverage, (-1, -1), Some(0..=2), Some(-2..=0)),
+            "...\n+3.\n+1.\n"
+        );
+    }
+}
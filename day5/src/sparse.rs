@@ -0,0 +1,30 @@
+use std::collections::HashMap;
+
+/// Coverage counts for only the points some line covers, so the grid's size does not matter
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseCoverage {
+    counts: HashMap<(i32, i32), u32>,
+}
+
+impl SparseCoverage {
+    /// Adds one to the count at `point`
+    pub fn add(&mut self, point: (i32, i32)) {
+        *self.counts.entry(point).or_insert(0) += 1;
+    }
+
+    /// Number of lines covering `point`
+    #[cfg(test)]
+    pub fn get(&self, point: (i32, i32)) -> u32 {
+        self.counts.get(&point).copied().unwrap_or(0)
+    }
+
+    /// Coverage of every covered point, in no particular order
+    pub fn counts(&self) -> impl Iterator<Item = u32> + '_ {
+        self.counts.values().copied()
+    }
+
+    /// Number of points covered by at least `threshold` lines
+    pub fn danger_score(&self, threshold: u32) -> usize {
+        self.counts().filter(|&count| count >= threshold).count()
+    }
+}
@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+
+use crate::{gcd, VentLine};
+
+/// Identifies the infinite line a segment lies on: its smallest lattice step, pointing right (or down for
+/// vertical lines), and the cross product of any point on it with that step
+type LineKey = ((i64, i64), i128);
+
+/// A line as its start, smallest lattice step and number of steps, so that its lattice points are
+/// `start + k * step` for `k` in `0..=steps`
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    start: (i64, i64),
+    step: (i64, i64),
+    steps: i64,
+}
+
+impl Segment {
+    fn new(vent_line: &VentLine) -> Self {
+        let (dy, dx) = vent_line.slope;
+        let steps = gcd(dx, dy);
+        let step = if steps == 0 {
+            (0, 0)
+        } else {
+            (dx / steps, dy / steps)
+        };
+
+        Self {
+            start: (vent_line.start.0 as i64, vent_line.start.1 as i64),
+            step,
+            steps,
+        }
+    }
+
+    fn point(&self, k: i64) -> (i64, i64) {
+        (
+            self.start.0 + k * self.step.0,
+            self.start.1 + k * self.step.1,
+        )
+    }
+
+    /// Whether `point` is one of the segment's lattice points
+    fn covers(&self, point: (i64, i64)) -> bool {
+        let offset = (point.0 - self.start.0, point.1 - self.start.1);
+        if self.steps == 0 {
+            return offset == (0, 0);
+        }
+
+        cross(offset, self.step) == 0 && (0..=self.steps).contains(&offset_steps(offset, self.step))
+    }
+
+    /// The line the segment lies on and the range of positions it covers along it; see [line_position]
+    fn line_span(&self) -> (LineKey, (i64, i64)) {
+        let step = if self.step.0 < 0 || (self.step.0 == 0 && self.step.1 < 0) {
+            (-self.step.0, -self.step.1)
+        } else {
+            self.step
+        };
+        let key = (step, cross(self.start, step));
+        let (first, last) = (
+            line_position(self.start, step),
+            line_position(self.point(self.steps), step),
+        );
+
+        (key, (first.min(last), first.max(last)))
+    }
+
+    /// The lattice point where two non-parallel segments cross, if they cross at one
+    fn crossing(&self, other: &Segment) -> Option<(i64, i64)> {
+        let offset = (other.start.0 - self.start.0, other.start.1 - self.start.1);
+        let denominator = cross(self.step, other.step);
+        // Solve `self.start + s * self.step == other.start + t * other.step` for whole `s` and `t`
+        let s_numerator = cross(offset, other.step);
+        let t_numerator = cross(offset, self.step);
+        if s_numerator % denominator != 0 || t_numerator % denominator != 0 {
+            return None;
+        }
+        let (s, t) = (
+            (s_numerator / denominator) as i64,
+            (t_numerator / denominator) as i64,
+        );
+
+        ((0..=self.steps).contains(&s) && (0..=other.steps).contains(&t)).then(|| self.point(s))
+    }
+}
+
+/// Cross product, widened since the products of far apart coordinates may not fit in an `i64`
+fn cross(a: (i64, i64), b: (i64, i64)) -> i128 {
+    a.0 as i128 * b.1 as i128 - a.1 as i128 * b.0 as i128
+}
+
+/// Number of `step`s in an `offset` known to be collinear with it; `step` is primitive, so any collinear
+/// lattice offset is a whole number of steps
+fn offset_steps(offset: (i64, i64), step: (i64, i64)) -> i64 {
+    if step.0 != 0 {
+        offset.0 / step.0
+    } else {
+        offset.1 / step.1
+    }
+}
+
+/// Position of a lattice point along a line with the primitive `step`; consecutive lattice points on the line
+/// are one position apart
+fn line_position(point: (i64, i64), step: (i64, i64)) -> i64 {
+    if step.0 != 0 {
+        point.0.div_euclid(step.0)
+    } else {
+        point.1.div_euclid(step.1)
+    }
+}
+
+/// The ranges of positions covered by at least two of the `spans` on one line, in order and disjoint
+fn double_covered(spans: &[(i64, i64)]) -> Vec<(i64, i64)> {
+    let mut events: Vec<(i64, i32)> = spans
+        .iter()
+        .flat_map(|&(first, last)| [(first, 1), (last + 1, -1)])
+        .collect();
+    events.sort_unstable();
+
+    let mut ranges: Vec<(i64, i64)> = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, change) in events {
+        let was_double = depth >= 2;
+        depth += change;
+        match (was_double, depth >= 2) {
+            (false, true) => start = position,
+            (true, false) if position > start => match ranges.last_mut() {
+                Some(last) if last.1 + 1 == start => last.1 = position - 1,
+                _ => ranges.push((start, position - 1)),
+            },
+            _ => {}
+        }
+    }
+
+    ranges
+}
+
+/// Whether `position` falls in one of the ordered, disjoint `ranges`
+fn in_ranges(ranges: &[(i64, i64)], position: i64) -> bool {
+    let idx = ranges.partition_point(|&(_, last)| last < position);
+    ranges.get(idx).is_some_and(|&(first, _)| first <= position)
+}
+
+/// Counts the lattice points covered by two or more lines straight from their geometry, without a grid.
+///
+/// Collinear lines are grouped by the line they lie on and counted as ranges of positions along it, so even
+/// overlaps billions of points long take no more memory than the lines themselves. Crossings between other
+/// lines are found by sweeping from left to right; each line is only compared with the earlier lines whose
+/// `x` range has not ended before it starts and whose `y` range overlaps its own, and crossings that fall in
+/// a collinear overlap are not counted twice. Lines are rasterized exactly, as with
+/// [crate::Rasterization::Exact].
+pub fn count_overlaps<'a>(vent_lines: impl IntoIterator<Item = &'a VentLine>) -> u64 {
+    // Copies of the same single point only need comparing with the other lines once
+    let mut single_points: HashMap<(i64, i64), (usize, usize)> = HashMap::new();
+    let mut vent_lines: Vec<(&VentLine, Segment)> = vent_lines
+        .into_iter()
+        .map(|vent_line| (vent_line, Segment::new(vent_line)))
+        .filter(|(_, segment)| {
+            if segment.steps > 0 {
+                return true;
+            }
+            let (copies, _) = single_points.entry(segment.start).or_default();
+            *copies += 1;
+            *copies == 1
+        })
+        .collect();
+    vent_lines.sort_by_key(|(vent_line, _)| vent_line.x_min);
+
+    // Collinear overlaps, per supporting line
+    let mut spans: HashMap<LineKey, Vec<(i64, i64)>> = HashMap::new();
+    for (_, segment) in vent_lines.iter().filter(|(_, segment)| segment.steps > 0) {
+        let (key, span) = segment.line_span();
+        spans.entry(key).or_default().push(span);
+    }
+    let collinear: HashMap<LineKey, Vec<(i64, i64)>> = spans
+        .into_iter()
+        .map(|(key, spans)| (key, double_covered(&spans)))
+        .filter(|(_, ranges)| !ranges.is_empty())
+        .collect();
+    // The line the segment lies on, if `point` falls in a collinear overlap along it
+    let collinear_at = |segment: &Segment, point: (i64, i64)| {
+        let (key, _) = segment.line_span();
+        collinear
+            .get(&key)
+            .filter(|ranges| in_ranges(ranges, line_position(point, key.0)))
+            .map(|_| key)
+    };
+
+    // Crossings, with the lines whose collinear overlaps already hold them, and single points covered by
+    // anything else
+    let mut crossings: HashMap<(i64, i64), Vec<LineKey>> = HashMap::new();
+    let mut active: Vec<&(&VentLine, Segment)> = vec![];
+    for entry in &vent_lines {
+        let (vent_line, segment) = entry;
+        active.retain(|(other_line, _)| other_line.x_max >= vent_line.x_min);
+
+        for (other_line, other) in &active {
+            if other_line.y_max < vent_line.y_min || other_line.y_min > vent_line.y_max {
+                continue;
+            }
+            match (segment.steps == 0, other.steps == 0) {
+                // Distinct single points never overlap
+                (true, true) => {}
+                (true, false) if other.covers(segment.start) => {
+                    single_points.entry(segment.start).or_default().1 += 1;
+                }
+                (false, true) if segment.covers(other.start) => {
+                    single_points.entry(other.start).or_default().1 += 1;
+                }
+                (false, false) if cross(segment.step, other.step) != 0 => {
+                    if let Some(point) = segment.crossing(other) {
+                        let counted_on = crossings.entry(point).or_default();
+                        for key in [collinear_at(segment, point), collinear_at(other, point)]
+                            .into_iter()
+                            .flatten()
+                        {
+                            if !counted_on.contains(&key) {
+                                counted_on.push(key);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        active.push(entry);
+    }
+
+    let collinear_count: u64 = collinear
+        .values()
+        .flatten()
+        .map(|&(first, last)| (last - first + 1) as u64)
+        .sum();
+    // A crossing counts once: it is new when no collinear overlap holds it, and was counted once too many
+    // times when the overlaps of several lines crossing there all do
+    let crossing_count = crossings
+        .values()
+        .filter(|counted_on| counted_on.is_empty())
+        .count() as u64;
+    let recounted: u64 = crossings
+        .values()
+        .map(|counted_on| counted_on.len().saturating_sub(1) as u64)
+        .sum();
+    // Points covered by two lines that are not single points were counted as collinear overlaps or crossings
+    let single_point_count = single_points
+        .values()
+        .filter(|&&(copies, lines)| copies + lines >= 2 && lines < 2)
+        .count() as u64;
+
+    collinear_count - recounted + crossing_count + single_point_count
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::load_input_data;
+
+    fn overlaps(input: &str) -> u64 {
+        count_overlaps(&load_input_data(input))
+    }
+
+    #[test]
+    fn test_collinear_overlaps() {
+        assert_eq!(overlaps("0,0 -> 10,0\n5,0 -> 15,0"), 6);
+        assert_eq!(overlaps("0,0 -> 10,0\n15,0 -> 5,0"), 6);
+        assert_eq!(overlaps("0,0 -> 10,0\n11,0 -> 15,0"), 0);
+        assert_eq!(overlaps("0,0 -> 6,3\n8,4 -> 2,1"), 3);
+        // A point on the line between two lattice points is not covered
+        assert_eq!(overlaps("0,0 -> 6,3\n1,0 -> 7,3"), 0);
+        // Three lines over the same points still count each point once
+        assert_eq!(overlaps("0,0 -> 4,4\n1,1 -> 3,3\n3,3 -> 2,2"), 3);
+        // Overlaps that touch end to end are one run of points
+        assert_eq!(overlaps("0,0 -> 4,0\n2,0 -> 6,0\n4,0 -> 8,0"), 5);
+    }
+
+    #[test]
+    fn test_crossing_overlaps() {
+        // The diagonals cross between lattice points
+        assert_eq!(overlaps("0,0 -> 1,1\n0,1 -> 1,0"), 0);
+        assert_eq!(overlaps("0,0 -> 2,10\n0,5 -> 3,5"), 1);
+        assert_eq!(overlaps("0,0 -> 2,10\n0,4 -> 3,4"), 0);
+        // Lines crossing inside a collinear overlap do not add to it
+        assert_eq!(
+            overlaps("0,0 -> 10,0\n5,0 -> 15,0\n7,-3 -> 7,3\n3,-3 -> 3,3"),
+            6 + 1
+        );
+        // Neither do collinear overlaps crossing each other
+        assert_eq!(
+            overlaps("0,0 -> 10,0\n5,0 -> 15,0\n7,-3 -> 7,3\n7,-5 -> 7,1"),
+            6 + 5 - 1
+        );
+        // Three lines crossing at one point count it once
+        assert_eq!(overlaps("0,0 -> 4,4\n0,4 -> 4,0\n2,0 -> 2,4"), 1);
+    }
+
+    #[test]
+    fn test_single_point_overlaps() {
+        // Single points only overlap lines passing through them, or each other
+        assert_eq!(overlaps("1,5 -> 1,5\n0,0 -> 2,10\n2,2 -> 2,2"), 1);
+        assert_eq!(overlaps("2,2 -> 2,2\n2,2 -> 2,2\n3,3 -> 3,3"), 1);
+        // Already counted as the crossing of the two other lines
+        assert_eq!(overlaps("2,2 -> 2,2\n0,0 -> 4,4\n0,4 -> 4,0"), 1);
+    }
+
+    #[test]
+    fn test_enormous_extents() {
+        let input = "-500000000,-500000000 -> 500000000,500000000\n\
+                     -500000000,500000000 -> 500000000,-500000000\n\
+                     -7,-7 -> 7,-7\n\
+                     499999990,499999990 -> 499999999,499999999";
+
+        // The diagonals cross at the origin and meet both ends of the short line, while the last line lies along
+        // the first diagonal for ten points
+        assert_eq!(overlaps(input), 1 + 2 + 10);
+    }
+
+    #[test]
+    fn test_long_collinear_overlaps() {
+        let input = "0,0 -> 1000000000,0\n\
+                     1000000005,0 -> 5,0\n\
+                     -600000000,-600000000 -> 600000000,600000000\n\
+                     0,0 -> 900000000,900000000\n\
+                     300000000,0 -> 300000000,5";
+
+        // The diagonals share 600000001 points and the horizontal lines 999999996. The vertical line crosses
+        // inside the horizontal overlap, and the diagonals meet the first horizontal line at the origin, inside
+        // their own overlap
+        assert_eq!(overlaps(input), 600_000_001 + 999_999_996);
+    }
+
+    #[test]
+    fn test_matches_sparse_coverage() {
+        // Lines with assorted slopes from a small linear congruential generator; the smaller field gives many
+        // collinear overlaps crossing each other
+        for (size, line_count) in [(25, 60), (8, 80)] {
+            let mut state: u32 = 12345;
+            let mut next = || {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) % size
+            };
+            let input: String = (0..line_count)
+                .map(|_| format!("{},{} -> {},{}\n", next(), next(), next(), next()))
+                .collect();
+
+            let mut vent_grid = crate::VentGrid::new(load_input_data(&input)).unwrap();
+            for include_angled in [false, true] {
+                let expected = vent_grid
+                    .calculate_sparse_coverage(include_angled)
+                    .danger_score(crate::DEFAULT_DANGER_THRESHOLD);
+                let lines = vent_grid
+                    .vent_lines
+                    .iter()
+                    .filter(|vent_line| include_angled || !vent_line.is_angled());
+                assert_eq!(count_overlaps(lines), expected as u64);
+            }
+        }
+    }
+}